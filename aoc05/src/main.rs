@@ -1,10 +1,10 @@
-use intcode::{Intcode, intcode_from_file, run, run_with_io};
+use intcode::{intcode_from_file, run_with_io};
 use std::io;
 
 fn main() -> io::Result<()> {
     let file = "./resources/input";
-    let input = intcode_from_file(file)?;
-    let output = run_with_io(input, vec![1]);
-    println!("Output is {:?}", output);
+    let mut code = intcode_from_file(file)?;
+    run_with_io(&mut code, vec![1])?;
+    println!("Output is {:?}", code.output);
     Ok(())
 }
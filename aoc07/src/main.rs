@@ -1,51 +1,42 @@
+use intcode::pipeline::Pipeline;
 use intcode::{intcode_from_file, run_with_io, Intcode};
 use std::io;
 
 fn run_amplifier(code: &Intcode, phase_setting: i64, input: i64) -> i64 {
-    run_with_io(code, vec![phase_setting, input])
-        .output
-        .pop()
-        .unwrap()
+    let mut amp = code.clone();
+    run_with_io(&mut amp, vec![phase_setting, input]).expect("Amplifier crashed");
+    amp.output.pop().unwrap()
 }
 
-fn run_amplifiers(code: &Intcode, phases: Vec<i64>) -> i64 {
-    let mut input = 0;
-    for i in phases {
-        input = run_amplifier(code, i, input);
+// Wires one amplifier per phase in a line, optionally feeding the last one back into the first
+fn amplifiers(code: &Intcode, phases: &[i64], feedback: bool) -> i64 {
+    let mut pipeline = Pipeline::new();
+    let amps: Vec<usize> = phases
+        .iter()
+        .map(|phase| {
+            let amp = pipeline.add_node(code.clone());
+            pipeline.seed(amp, &[*phase]);
+            amp
+        })
+        .collect();
+    for pair in amps.windows(2) {
+        pipeline.connect(pair[0], pair[1]);
+    }
+    let last = amps[amps.len() - 1];
+    if feedback {
+        pipeline.connect(last, amps[0]);
     }
-    input
+    pipeline.seed(amps[0], &[0]);
+    let outcome = pipeline.run().expect("Amplifier crashed");
+    *outcome.outputs[last].last().unwrap()
 }
 
-fn run_amplifier_cont(code: &Intcode, input: i64) -> Intcode {
-    run_with_io(code, vec![input])
+fn run_amplifiers(code: &Intcode, phases: Vec<i64>) -> i64 {
+    amplifiers(code, &phases, false)
 }
 
-fn run_feedback(code: &Intcode, phases: &Vec<i64>) -> i64 {
-    let mut input = 0;
-    let mut amps: Vec<Intcode> = vec![code.clone(), code.clone(), code.clone(), code.clone(), code.clone()];
-    let mut output = 0;
-    for i in 0..5 {
-        amps[i] = run_amplifier_cont(&amps[i], phases[i]);
-    }
-    'outer: loop {
-        for i in 0..5 {
-            let mut amp = run_amplifier_cont(&amps[i], input);
-            let out = amp.output.pop();
-            if code.halted {
-                break 'outer;
-            }
-            match out {
-                None => break 'outer,
-                Some(x) => input = x,
-            }
-            if i == 4 {
-                output = out.unwrap();
-            }
-            amps[i] = amp;
-        }
-        println!("After loop, it's {}", output);
-    }
-    return output;
+fn run_feedback(code: &Intcode, phases: &[i64]) -> i64 {
+    amplifiers(code, phases, true)
 }
 
 fn permutations(phases: Vec<i64>) -> Vec<Vec<i64>> {
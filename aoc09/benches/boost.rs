@@ -0,0 +1,35 @@
+// Times BOOST interpreted and compiled by build.rs; run with `cargo bench`.
+use intcode::{intcode_from_file, run_with_io};
+use std::time::{Duration, Instant};
+
+mod boost {
+    include!(concat!(env!("OUT_DIR"), "/boost.rs"));
+}
+
+fn time<F: FnMut()>(name: &str, runs: u32, mut f: F) {
+    let mut best = Duration::from_secs(u64::MAX);
+    for _ in 0..runs {
+        let start = Instant::now();
+        f();
+        best = best.min(start.elapsed());
+    }
+    println!("{:<24} {:>10.3} ms", name, best.as_secs_f64() * 1000.0);
+}
+
+fn main() {
+    let file = format!("{}/resources/input", env!("CARGO_MANIFEST_DIR"));
+    let program = intcode_from_file(file).unwrap();
+    let mut expected = program.clone();
+    run_with_io(&mut expected, vec![2]).unwrap();
+
+    time("day 9 (interpreted)", 5, || {
+        let mut code = program.clone();
+        run_with_io(&mut code, vec![2]).unwrap();
+        assert_eq!(code.output, expected.output);
+    });
+    time("day 9 (compiled)", 5, || {
+        let mut compiled = boost::new();
+        compiled.run_with_io(vec![2]).unwrap();
+        assert_eq!(compiled.code().output, expected.output);
+    });
+}
@@ -0,0 +1,11 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// Compiles BOOST to Rust so benches/boost.rs can time it against the interpreter
+fn main() {
+    let code = intcode::intcode_from_file("resources/input").expect("Cannot load resources/input");
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("boost.rs");
+    fs::write(out, intcode::compile::compile(&code)).expect("Cannot write the compiled program");
+    println!("cargo:rerun-if-changed=resources/input");
+}
@@ -3,9 +3,9 @@ use std::io;
 
 fn main() -> io::Result<()> {
     let file = "./resources/input";
-    let input = intcode_from_file(file)?;
-    let output = run_with_io(&input, vec![2]);
+    let mut code = intcode_from_file(file)?;
+    run_with_io(&mut code, vec![2])?;
 
-    println!("Result is {:?}", output.output);
+    println!("Result is {:?}", code.output);
     Ok(())
 }
@@ -1,4 +1,6 @@
-use intcode::{Intcode, intcode_from_file, run_with_io};
+use intcode::{Intcode, intcode_from_file};
+use intcode::devices::run_with;
+use std::cell::RefCell;
 use std::io;
 use std::collections::HashMap;
 
@@ -77,17 +79,11 @@ struct World {
     brain: Intcode,
 }
 
-fn run_once(world: &World) -> World{
-    let colour = get_color(&world.field, world.pos);
-    println!("Colour is {}", colour);
-    let out = run_with_io(&world.brain, vec![colour as i64]);
-    let control = &out.output;
-    println!("Painter said {:?}", control);
-    let new_color = control[0];
-    let new_dir = match control[1] {
+fn act(world: &mut World, new_color: i64, turn: i64) {
+    let new_dir = match turn {
         0 => rotate(world.dir, Rotation::Left),
         1 => rotate(world.dir, Rotation::Right),
-        _ => panic!("Unknown direction {}", control[1])
+        _ => panic!("Unknown direction {}", turn)
     };
     println!("Rotated from {:?} to {:?}", world.dir, new_dir);
     let pos = world.pos;
@@ -97,30 +93,40 @@ fn run_once(world: &World) -> World{
         Direction::Down => (pos.0, pos.1-1),
         Direction::Left => (pos.0-1, pos.1),
     };
-    let mut new_brain = out.clone();
-    let mut new_field = world.field.clone();
-    paint(&mut new_field, pos, new_color);
-    new_brain.output = vec![];
-    World {
-        dir: new_dir,
-        pos: new_pos,
-        field: new_field,
-        brain: new_brain,
-    }
+    paint(&mut world.field, pos, new_color);
+    world.dir = new_dir;
+    world.pos = new_pos;
 }
 
 fn run(world: World) -> Field{
-    let mut new_world = world;
-    loop {
-        new_world = run_once(&new_world);
-        println!("Dir: {:?}, pos: {:?}, field: {:?}", new_world.dir, new_world.pos, new_world.field);
-        //pause();
-        if new_world.brain.halted {
-            println!("Done: {:?}", new_world);
-            println!("Painted {} fields", new_world.field.len());
-            break;
-        }
+    let mut brain = world.brain.clone();
+    let world = RefCell::new(world);
+    {
+        // The camera reads the panel under the robot, every pair of outputs paints and moves it
+        let mut camera = || {
+            let world = world.borrow();
+            let colour = get_color(&world.field, world.pos);
+            println!("Colour is {}", colour);
+            Some(colour)
+        };
+        let mut control = vec![];
+        let mut motor = |val| {
+            control.push(val);
+            if control.len() == 2 {
+                println!("Painter said {:?}", control);
+                let mut world = world.borrow_mut();
+                act(&mut world, control[0], control[1]);
+                control.clear();
+                println!("Dir: {:?}, pos: {:?}, field: {:?}", world.dir, world.pos, world.field);
+                //pause();
+            }
+        };
+        run_with(&mut brain, &mut camera, &mut motor).expect("Painter crashed");
     }
+    let mut new_world = world.into_inner();
+    new_world.brain = brain;
+    println!("Done: {:?}", new_world);
+    println!("Painted {} fields", new_world.field.len());
     return new_world.field;
 }
 
@@ -1,4 +1,4 @@
-use intcode::{intcode_from_file, run_with_io};
+use intcode::{intcode_from_file, Step};
 use std::io;
 use std::io::prelude::*;
 
@@ -103,30 +103,24 @@ fn xy_index(x: i64, y: i64) -> usize {
 
 
 // Returns the ball x position
-fn update(world: &mut World, output: Vec<i64>) -> (i64, i64) {
-    let mut ball_x = 0;
+fn update(world: &mut World, x: i64, y: i64, tipe: i64) -> (i64, i64) {
+    let mut ball_x = -1;
     let mut paddle_x = -1;
-    for j in 0..output.len() / 3 {
-        let i = j * 3;
-        let x = output[i];
-        let y = output[i + 1];
-        let tipe = output[i + 2];
-        if x == -1 && y == 0 {
-            world.score = tipe;
+    if x == -1 && y == 0 {
+        world.score = tipe;
+    } else {
+        println!("New tile: {} {} {}", x, y, tipe);
+        let tile = Tile::new(x, y, tipe);
+        if tile.tipe == TileType::Ball {
+            ball_x = tile.x;
+        } else if tile.tipe == TileType::Paddle {
+            paddle_x = tile.x;
+        }
+        let index = xy_index(x, y);
+        if world.tiles.len() <= index {
+            world.tiles.push(tile);
         } else {
-            println!("New tile: {} {} {}", x, y, tipe);
-            let tile = Tile::new(x, y, tipe);
-            if tile.tipe == TileType::Ball {
-                ball_x = tile.x;
-            } else if tile.tipe == TileType::Paddle {
-                paddle_x = tile.x;
-            }
-            let index = xy_index(x, y);
-            if world.tiles.len() <= index {
-                world.tiles.push(tile);
-            } else {
-                world.tiles[index] = tile;
-            }
+            world.tiles[index] = tile;
         }
     }
     (ball_x, paddle_x)
@@ -142,21 +136,33 @@ fn main() -> io::Result<()> {
     code.data[0] = 2;
     let mut ball_x = 0;
     let mut paddle_x = 0;
-    let mut joy_in = 0;
-    while !code.halted {
-        code = run_with_io(&code, vec![joy_in]);
-        let (new_ball_x, new_paddle_x) = update(&mut world, code.output);
-        display(&world);
-        code.output = vec![];
-        if new_paddle_x != -1 {
-            paddle_x = new_paddle_x;
-        }
-        if new_ball_x != -1 {
-            ball_x = new_ball_x;
+    let mut pending = vec![];
+    loop {
+        match code.step()? {
+            Step::Continue => {}
+            Step::Output(val) => {
+                pending.push(val);
+                if pending.len() == 3 {
+                    let (new_ball_x, new_paddle_x) = update(&mut world, pending[0], pending[1], pending[2]);
+                    pending.clear();
+                    if new_paddle_x != -1 {
+                        paddle_x = new_paddle_x;
+                    }
+                    if new_ball_x != -1 {
+                        ball_x = new_ball_x;
+                    }
+                }
+            }
+            Step::NeedInput => {
+                display(&world);
+                let joy_in = norm(ball_x - paddle_x);
+                println!("Input: {}", joy_in);
+                code.push_input(joy_in);
+                pause();
+            }
+            Step::Halted => break,
         }
-        joy_in = norm(ball_x - paddle_x);
-        println!("Input: {}", joy_in);
-        pause();
     }
+    display(&world);
     Ok(())
 }
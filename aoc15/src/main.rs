@@ -1,4 +1,4 @@
-use intcode::{intcode_from_file, run_with_io, Intcode};
+use intcode::{intcode_from_file, Intcode, Step};
 use std::io;
 use std::io::prelude::*;
 
@@ -77,10 +77,15 @@ impl<T> Map<T> {
 }
 
 fn step(code: &Intcode, dir: Direction) -> (Response, Intcode) {
-    let input = dir_to_int(dir);
-    let mut outcode = run_with_io(code, vec![input]);
-    let response = int_to_res(outcode.output.pop().unwrap());
-    (response, outcode)
+    let mut outcode = code.fork();
+    outcode.push_input(dir_to_int(dir));
+    loop {
+        match outcode.step().expect("Droid crashed") {
+            Step::Continue => {}
+            Step::Output(r) => return (int_to_res(r), outcode),
+            s => panic!("Droid stopped without answering: {:?}", s),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -233,7 +238,7 @@ fn display(map: &Map<TileType>, r_x: usize, r_y: usize) {
 }
 
 fn explore(map: &mut Map<TileType>, code: &Intcode, x: usize, y: usize) {
-    let mut code = code.clone();
+    let mut code = code.fork();
     let (mut x, mut y) = (x, y);
     let mut dirty = false;
     let mut dijk_map = build_map(&map, TileType::Unknown);
@@ -1,3 +1,4 @@
+use intcode::ascii::{read_output, send_line};
 use intcode::{intcode_from_file, run_with_io};
 use std::io;
 
@@ -112,8 +113,8 @@ fn get_intersections(map: &Map<TileType>) -> Vec<(usize, usize)> {
 }
 
 fn part1() -> io::Result<()> {
-    let code = intcode_from_file("./resources/input")?;
-    let out = run_with_io(&code, vec![]);
+    let mut out = intcode_from_file("./resources/input")?;
+    run_with_io(&mut out, vec![])?;
     for x in &out.output {
         print!("{}", std::char::from_u32(*x as u32).unwrap().to_string());
     }
@@ -145,19 +146,13 @@ fn part1() -> io::Result<()> {
     Ok(())
 }
 
-fn add_sequence(input: &mut Vec<i64>, seq: &str) {
-    for c in seq.as_bytes() {
-        input.push(*c as i64);
-    }
-    input.push('\n' as i64); // Newline
-}
-
 fn main() -> io::Result<()> {
     let mut code = intcode_from_file("./resources/input")?;
     code.data[0] = 2;
-    let out = run_with_io(&code, vec![]);
-    for x in &out.output {
-        print!("{}", std::char::from_u32(*x as u32).unwrap().to_string());
+    let mut out = code.clone();
+    run_with_io(&mut out, vec![])?;
+    for line in read_output(&mut out).lines {
+        println!("{}", line);
     }
     let path = "L,12,L,8,R,10,R,10,L,6,L,4,L,12,L,12,L,8,R,10,R,10,L,6,L,4,L,12,R,10,L,8,L,4,R,10,L,6,L,4,L,12,L,12,L,8,R,10,R,10,R,10,L,8,L,4,R,10,L,6,L,4,L,12,R,10,L,8,L,4,R,10";
     println!("Path should be: {}", path);
@@ -166,33 +161,29 @@ fn main() -> io::Result<()> {
     let b = "L,6,L,4,L,12";
     let c = "R,10,L,8,L,4,R,10";
 
-    let mut input: Vec<i64> = vec![];
-    add_sequence(&mut input, mainseq);
-    add_sequence(&mut input, a);
-    add_sequence(&mut input, b);
-    add_sequence(&mut input, c);
-    let video_feed = 'n'; // 'y' or 'n' to select interactive video feed or not
-    input.push(video_feed as i64);
-    input.push('\n' as i64);
-    let out = run_with_io(&code, input);
+    let mut out = code.clone();
+    for seq in &[mainseq, a, b, c] {
+        send_line(&mut out, seq)?;
+    }
+    let video_feed = "n"; // "y" or "n" to select interactive video feed or not
+    send_line(&mut out, video_feed)?;
+    run_with_io(&mut out, vec![])?;
+    let screen = read_output(&mut out);
     let mut y = 0;
     let mut reached_video = false;
-    for c in out.output {
-        if c == 10 {
-            y += 1;
-            if y > 55 && !reached_video {
-                reached_video = true;
-                y = 0;
-            }
-            if video_feed == 'y' && reached_video && y % 50 == 0 {
-                pause();
-            }
+    for line in screen.lines {
+        println!("{}", line);
+        y += 1;
+        if y > 55 && !reached_video {
+            reached_video = true;
+            y = 0;
         }
-        if c > 256 {
-            println!("Space dust collected: {}", c);
-            break;
+        if video_feed == "y" && reached_video && y % 50 == 0 {
+            pause();
         }
-        print!("{}", std::char::from_u32(c as u32).unwrap().to_string());
+    }
+    for dust in screen.answers {
+        println!("Space dust collected: {}", dust);
     }
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,6 +1,9 @@
-use intcode::{intcode_from_file, run_with_io};
 use std::io;
 
+mod beam {
+    include!(concat!(env!("OUT_DIR"), "/beam.rs"));
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 struct Map<T> {
     tiles: Vec<T>,
@@ -28,8 +31,6 @@ fn square_fits(map: &Map<i64>, x: usize, y: usize, width: usize, height: usize)
 }
 
 fn main() -> io::Result<()> {
-    let file = "./resources/input";
-    let code = intcode_from_file(file)?;
     let width = 800;
     let height = 1100;
     let tiles = vec![0; width * height];
@@ -45,8 +46,9 @@ fn main() -> io::Result<()> {
     for x in 0..width {
         y = last_y;
         loop {
-            let out = run_with_io(&code, vec![x as i64, y as i64]);
-            let pulling = out.output[0];
+            let mut out = beam::new();
+            out.run_with_io(vec![x as i64, y as i64])?;
+            let pulling = out.code().output[0];
             if pulling == 1 {
                 let i = map.xy_index(x, y);
                 map.tiles[i] = pulling;
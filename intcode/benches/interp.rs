@@ -0,0 +1,99 @@
+// Times the interpreter on real programs against the one it replaced; run with `cargo bench`.
+use intcode::batch::run_batch;
+use intcode::{intcode_from_file, run_with_io, Intcode};
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+// The interpreter before it ran in place, to compare against
+#[path = "reference/vec_interp.rs"]
+mod vec_interp;
+
+fn load(day: &str) -> Intcode {
+    let file = format!("{}/../{}/resources/input", env!("CARGO_MANIFEST_DIR"), day);
+    intcode_from_file(file).unwrap()
+}
+
+fn time<F: FnMut()>(name: &str, runs: u32, mut f: F) {
+    let mut best = Duration::from_secs(u64::MAX);
+    for _ in 0..runs {
+        let start = Instant::now();
+        f();
+        best = best.min(start.elapsed());
+    }
+    println!("{:<24} {:>10.3} ms", name, best.as_secs_f64() * 1000.0);
+}
+
+// Floods the day 15 maze breadth first, copying the droid with `copy` for every move
+fn explore(droid: &Intcode, copy: fn(&Intcode) -> Intcode) -> usize {
+    let mut seen = HashSet::new();
+    seen.insert((0, 0));
+    let mut queue = VecDeque::new();
+    queue.push_back(((0, 0), copy(droid)));
+    while let Some(((x, y), droid)) = queue.pop_front() {
+        for &(dir, dx, dy) in &[(1, 0, -1), (2, 0, 1), (3, -1, 0), (4, 1, 0)] {
+            if !seen.insert((x + dx, y + dy)) {
+                continue;
+            }
+            let mut moved = copy(&droid);
+            run_with_io(&mut moved, vec![dir]).unwrap();
+            if moved.output.pop() != Some(0) {
+                queue.push_back(((x + dx, y + dy), moved));
+            }
+        }
+    }
+    seen.len()
+}
+
+fn main() {
+    // Day 9 part 2 runs a single long computation
+    let boost = load("aoc09");
+    time("day 9 (BOOST)", 5, || {
+        let mut code = boost.clone();
+        run_with_io(&mut code, vec![2]).unwrap();
+        assert_eq!(code.output.len(), 1);
+    });
+    let program = boost.data.dense();
+    time("day 9 (old interpreter)", 5, || {
+        let mut code = vec_interp::Intcode::from_data(program.clone());
+        vec_interp::run_with_io(&mut code, vec![2]).unwrap();
+        assert_eq!(code.output.len(), 1);
+    });
+    // The compiled version is timed by aoc09's own bench, which compiles it in its build script
+
+    // Day 19 restarts a short program for every probed coordinate
+    let beam = load("aoc19");
+    time("day 19 (100x100 probes)", 5, || {
+        let mut pulled = 0;
+        for x in 0..100 {
+            for y in 0..100 {
+                let mut code = beam.clone();
+                run_with_io(&mut code, vec![x, y]).unwrap();
+                pulled += code.output[0];
+            }
+        }
+        assert!(pulled > 0);
+    });
+    let program = beam.data.dense();
+    time("day 19 (old interpreter)", 5, || {
+        let mut pulled = 0;
+        for x in 0..100 {
+            for y in 0..100 {
+                let mut code = vec_interp::Intcode::from_data(program.clone());
+                vec_interp::run_with_io(&mut code, vec![x, y]).unwrap();
+                pulled += code.output[0];
+            }
+        }
+        assert!(pulled > 0);
+    });
+    time("day 19 (batched)", 5, || {
+        let probes = (0..100).flat_map(|x| (0..100).map(move |y| vec![x, y]));
+        let pulled: i64 = run_batch(&beam, probes).into_iter().map(|out| out.unwrap()[0]).sum();
+        assert!(pulled > 0);
+    });
+
+    // Day 15 searches by copying the droid at every step
+    let droid = load("aoc15");
+    let tiles = explore(&droid, Intcode::clone);
+    time("day 15 (clone per move)", 5, || assert_eq!(explore(&droid, Intcode::clone), tiles));
+    time("day 15 (fork per move)", 5, || assert_eq!(explore(&droid, Intcode::fork), tiles));
+}
@@ -0,0 +1,199 @@
+// The interpreter as it was before it ran in place: every instruction is decoded
+// into a fresh Vec, decoded again by the function that runs it, memory is a plain
+// Vec and every read copies the remaining input. Kept as a baseline for the
+// benchmarks, so faults just come back as `None`.
+
+#[derive(Clone, Copy)]
+enum Op {
+    Stop,
+    Add,
+    Mul,
+    Read,
+    Write,
+    JumpIfTrue,
+    JumpIfFalse,
+    LessThan,
+    Equals,
+    AdjustRelBase,
+}
+
+#[derive(Clone, Copy)]
+enum ParameterMode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+#[derive(Clone, Copy)]
+struct Arg {
+    value: i64,
+    mode: ParameterMode,
+}
+
+struct Operation {
+    opcode: Op,
+    args: Vec<Arg>,
+}
+
+enum Step {
+    Continue,
+    Output(i64),
+    NeedInput,
+    Halted,
+}
+
+pub struct Intcode {
+    pos: usize,
+    rel_base: usize,
+    data: Vec<i64>,
+    input: Vec<i64>,
+    pub output: Vec<i64>,
+}
+
+impl Intcode {
+    pub fn from_data(data: Vec<i64>) -> Intcode {
+        Intcode {
+            pos: 0,
+            rel_base: 0,
+            data,
+            input: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    fn deref(&self, at: usize) -> i64 {
+        self.data.get(at).cloned().unwrap_or(0)
+    }
+
+    fn arg(&self, offset: usize) -> i64 {
+        self.deref(self.pos + offset)
+    }
+
+    fn param_mode(&self, op: i64, shift: i64) -> Option<ParameterMode> {
+        match (op / shift) % 10 {
+            0 => Some(ParameterMode::Position),
+            1 => Some(ParameterMode::Immediate),
+            2 => Some(ParameterMode::Relative),
+            _ => None,
+        }
+    }
+
+    fn op(&self) -> Option<Operation> {
+        let code = self.arg(0);
+        let (opcode, arity) = match code % 100 {
+            99 => (Op::Stop, 0),
+            1 => (Op::Add, 3),
+            2 => (Op::Mul, 3),
+            3 => (Op::Read, 1),
+            4 => (Op::Write, 1),
+            5 => (Op::JumpIfTrue, 2),
+            6 => (Op::JumpIfFalse, 2),
+            7 => (Op::LessThan, 3),
+            8 => (Op::Equals, 3),
+            9 => (Op::AdjustRelBase, 1),
+            _ => return None,
+        };
+        let mut args = Vec::new();
+        let mut shift = 100;
+        for i in 0..arity {
+            args.push(Arg {
+                value: self.arg(i + 1),
+                mode: self.param_mode(code, shift)?,
+            });
+            shift *= 10;
+        }
+        Some(Operation { opcode, args })
+    }
+
+    fn address(&self, arg: Arg) -> Option<usize> {
+        let addr = match arg.mode {
+            ParameterMode::Immediate => return None,
+            ParameterMode::Position => arg.value,
+            ParameterMode::Relative => arg.value + self.rel_base as i64,
+        };
+        if addr < 0 {
+            None
+        } else {
+            Some(addr as usize)
+        }
+    }
+
+    fn write_at(&mut self, arg: Arg, val: i64) -> Option<()> {
+        let addr = self.address(arg)?;
+        if addr >= self.data.len() {
+            self.data.extend(vec![0; addr - self.data.len() + 1]);
+        }
+        self.data[addr] = val;
+        Some(())
+    }
+
+    fn arg_to_val(&self, arg: Arg) -> Option<i64> {
+        match arg.mode {
+            ParameterMode::Immediate => Some(arg.value),
+            _ => Some(self.deref(self.address(arg)?)),
+        }
+    }
+
+    fn step(&mut self) -> Option<Step> {
+        match self.op()?.opcode {
+            Op::Stop => return Some(Step::Halted),
+            Op::Add => self.do_op(|a, b| a + b)?,
+            Op::Mul => self.do_op(|a, b| a * b)?,
+            Op::LessThan => self.do_op(|a, b| (a < b) as i64)?,
+            Op::Equals => self.do_op(|a, b| (a == b) as i64)?,
+            Op::Read => {
+                if self.input.is_empty() {
+                    return Some(Step::NeedInput);
+                }
+                let args = self.op()?.args;
+                self.write_at(args[0], self.input[0])?;
+                self.pos += 2;
+                self.input = self.input[1..].to_vec();
+            }
+            Op::Write => {
+                let args = self.op()?.args;
+                let val = self.arg_to_val(args[0])?;
+                self.pos += 2;
+                return Some(Step::Output(val));
+            }
+            Op::JumpIfTrue => self.jump_if(|x| x != 0)?,
+            Op::JumpIfFalse => self.jump_if(|x| x == 0)?,
+            Op::AdjustRelBase => {
+                let args = self.op()?.args;
+                self.rel_base = (self.rel_base as i64 + self.arg_to_val(args[0])?) as usize;
+                self.pos += 2;
+            }
+        }
+        Some(Step::Continue)
+    }
+
+    fn do_op(&mut self, op: fn(i64, i64) -> i64) -> Option<()> {
+        let args = self.op()?.args;
+        let val = op(self.arg_to_val(args[0])?, self.arg_to_val(args[1])?);
+        self.write_at(args[2], val)?;
+        self.pos += 4;
+        Some(())
+    }
+
+    fn jump_if(&mut self, con: fn(i64) -> bool) -> Option<()> {
+        let args = self.op()?.args;
+        self.pos = if con(self.arg_to_val(args[0])?) {
+            self.arg_to_val(args[1])? as usize
+        } else {
+            self.pos + 3
+        };
+        Some(())
+    }
+}
+
+/// Queues `input` and runs until the machine halts or waits for more.
+pub fn run_with_io(code: &mut Intcode, input: Vec<i64>) -> Option<()> {
+    code.input.extend(input);
+    loop {
+        match code.step()? {
+            Step::Continue => {}
+            Step::Output(val) => code.output.push(val),
+            Step::Halted | Step::NeedInput => return Some(()),
+        }
+    }
+}
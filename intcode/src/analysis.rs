@@ -0,0 +1,267 @@
+use super::disasm::{format_operation, instruction_at};
+use super::{Intcode, Op, Operation, ParameterMode};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A straight run of instructions `start..end` that's only ever entered at `start`,
+/// as far as can be told without running the program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub start: usize,
+    pub end: usize,
+    /// Starts of the blocks control can go to next.
+    pub successors: Vec<usize>,
+    /// Ends in a jump whose target is only known at run time.
+    pub computed_jump: bool,
+}
+
+/// An instruction at `at` whose write goes to `target`, which holds code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeWrite {
+    pub at: usize,
+    pub target: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    /// In address order. Words that don't decode as instructions belong to no block.
+    pub blocks: Vec<Block>,
+    /// Code addresses appearing as immediate values, such as pushed return addresses.
+    /// Computed jumps probably land on one of these, so each starts a block.
+    pub entry_points: Vec<usize>,
+    pub code_writes: Vec<CodeWrite>,
+}
+
+fn immediates(op: &Operation) -> impl Iterator<Item = i64> + '_ {
+    op.args[..op.opcode.arity()]
+        .iter()
+        .filter(|arg| matches!(arg.mode, ParameterMode::Immediate))
+        .map(|arg| arg.value)
+}
+
+fn ends_block(op: Op) -> bool {
+    matches!(op, Op::JumpIfTrue | Op::JumpIfFalse | Op::Write | Op::Stop)
+}
+
+impl Analysis {
+    /// The block holding the instruction word at `addr`.
+    pub fn block_at(&self, addr: usize) -> Option<&Block> {
+        let i = match self.blocks.binary_search_by_key(&addr, |block| block.start) {
+            Ok(i) => i,
+            Err(0) => return None,
+            Err(i) => i - 1,
+        };
+        Some(&self.blocks[i]).filter(|block| addr < block.end)
+    }
+
+    /// The control flow graph in Graphviz DOT. Blocks ending in a computed jump point at a
+    /// `?` node, writes into code are dotted edges to the block that gets overwritten.
+    pub fn to_dot(&self, code: &Intcode) -> String {
+        let mut dot = String::from("digraph intcode {\n    node [shape=box, fontname=\"monospace\"];\n");
+        let written: BTreeSet<usize> = self
+            .code_writes
+            .iter()
+            .filter_map(|write| self.block_at(write.target))
+            .map(|block| block.start)
+            .collect();
+        for block in &self.blocks {
+            let mut label = String::new();
+            let mut ip = block.start;
+            while ip < block.end {
+                let op = instruction_at(code, ip).expect("blocks hold instructions");
+                label += &format!("{}: {}\\l", ip, format_operation(&op));
+                ip += op.opcode.arity() + 1;
+            }
+            let style = if written.contains(&block.start) { ", style=filled, fillcolor=yellow" } else { "" };
+            dot += &format!("    b{} [label=\"{}\"{}];\n", block.start, label, style);
+            for succ in &block.successors {
+                dot += &format!("    b{} -> b{};\n", block.start, succ);
+            }
+            if block.computed_jump {
+                dot += &format!("    b{} -> computed [style=dashed];\n", block.start);
+            }
+        }
+        if self.blocks.iter().any(|block| block.computed_jump) {
+            dot += "    computed [shape=diamond, label=\"?\"];\n";
+        }
+        for write in &self.code_writes {
+            if let (Some(from), Some(to)) = (self.block_at(write.at), self.block_at(write.target)) {
+                dot += &format!("    b{} -> b{} [style=dotted, label=\"writes {}\"];\n", from.start, to.start, write.target);
+            }
+        }
+        dot + "}\n"
+    }
+}
+
+/// Splits the program's dense memory into basic blocks with a linear sweep, like `disassemble`.
+pub fn analyze(code: &Intcode) -> Analysis {
+    let mut ops = BTreeMap::new();
+    let mut addr = 0;
+    while addr < code.data.len() {
+        match instruction_at(code, addr) {
+            Some(op) => {
+                ops.insert(addr, op);
+                addr += op.opcode.arity() + 1;
+            }
+            None => addr += 1,
+        }
+    }
+    let next = |addr: usize, op: &Operation| addr + op.opcode.arity() + 1;
+
+    let mut leaders = BTreeSet::new();
+    let mut entry_points = BTreeSet::new();
+    let mut after_data = true;
+    for (&addr, op) in &ops {
+        // Nothing falls through from data, so code after it has to be entered some other way
+        if after_data {
+            leaders.insert(addr);
+        }
+        for val in immediates(op) {
+            if val >= 0 && ops.contains_key(&(val as usize)) {
+                leaders.insert(val as usize);
+                if !matches!(op.opcode, Op::JumpIfTrue | Op::JumpIfFalse) {
+                    entry_points.insert(val as usize);
+                }
+            }
+        }
+        if ends_block(op.opcode) {
+            leaders.insert(next(addr, op));
+        }
+        after_data = !ops.contains_key(&next(addr, op));
+    }
+    leaders.retain(|addr| ops.contains_key(addr));
+
+    // Which block every instruction word belongs to
+    let mut owner = BTreeMap::new();
+    let mut blocks = Vec::new();
+    for &start in &leaders {
+        let mut ip = start;
+        let mut last = None;
+        while let Some(op) = ops.get(&ip) {
+            if ip != start && leaders.contains(&ip) {
+                break;
+            }
+            for word in ip..next(ip, op) {
+                owner.insert(word, start);
+            }
+            last = Some(*op);
+            ip = next(ip, op);
+            if ends_block(op.opcode) {
+                break;
+            }
+        }
+        let op = last.expect("leaders are instructions");
+        let mut successors = Vec::new();
+        let mut computed_jump = false;
+        let fall_through = ops.contains_key(&ip);
+        match op.opcode {
+            Op::Stop => {}
+            Op::JumpIfTrue | Op::JumpIfFalse => {
+                let [test, target, _] = op.args;
+                // A constant condition always goes the same way
+                let (taken, skipped) = match test.mode {
+                    ParameterMode::Immediate => {
+                        let taken = (test.value != 0) == (op.opcode == Op::JumpIfTrue);
+                        (taken, !taken)
+                    }
+                    _ => (true, true),
+                };
+                if taken {
+                    match target.mode {
+                        ParameterMode::Immediate => {
+                            if target.value >= 0 && ops.contains_key(&(target.value as usize)) {
+                                successors.push(target.value as usize);
+                            }
+                        }
+                        _ => computed_jump = true,
+                    }
+                }
+                if skipped && fall_through {
+                    successors.push(ip);
+                }
+            }
+            _ => {
+                if fall_through {
+                    successors.push(ip);
+                }
+            }
+        }
+        successors.dedup();
+        blocks.push(Block {
+            start,
+            end: ip,
+            successors,
+            computed_jump,
+        });
+    }
+
+    let mut code_writes = Vec::new();
+    for (&addr, op) in &ops {
+        if let Some(i) = op.opcode.write_arg() {
+            let arg = op.args[i];
+            if matches!(arg.mode, ParameterMode::Position) && arg.value >= 0 && owner.contains_key(&(arg.value as usize)) {
+                code_writes.push(CodeWrite {
+                    at: addr,
+                    target: arg.value as usize,
+                });
+            }
+        }
+    }
+
+    Analysis {
+        blocks,
+        entry_points: entry_points.into_iter().collect(),
+        code_writes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{analyze, Block, CodeWrite};
+    use crate::asm::assemble;
+    use crate::Intcode;
+
+    #[test]
+    fn blocks_and_graph() {
+        let program = assemble(
+            "
+                    IN [x]
+            loop:   JF [x], #done
+                    ADD [x], #-1, [x]
+                    MUL #ret, #1, [rb]
+                    JT #1, #sub
+            ret:    JT #1, #loop
+            sub:    OUT [x]
+                    JT #1, [rb]
+            done:   ADD #1101, #1, [done]
+                    HLT
+            x:      DATA 0
+            ",
+        )
+        .unwrap();
+        let code = Intcode::from_data(program);
+        let analysis = analyze(&code);
+        let block = |start, end, successors: Vec<usize>, computed_jump| Block { start, end, successors, computed_jump };
+        assert_eq!(
+            analysis.blocks,
+            vec![
+                block(0, 2, vec![2], false),
+                block(2, 5, vec![24, 5], false),
+                block(5, 16, vec![19], false),
+                block(16, 19, vec![2], false),
+                block(19, 21, vec![21], false),
+                block(21, 24, vec![], true),
+                block(24, 29, vec![], false),
+            ]
+        );
+        assert_eq!(analysis.entry_points, vec![16]);
+        assert_eq!(analysis.code_writes, vec![CodeWrite { at: 24, target: 24 }]);
+        assert_eq!(analysis.block_at(28).map(|b| b.start), Some(24));
+        assert_eq!(analysis.block_at(29), None);
+
+        let dot = analysis.to_dot(&code);
+        assert!(dot.starts_with("digraph intcode {\n"));
+        assert!(dot.contains("    b2 [label=\"2: JF [29], #24\\l\"];\n"));
+        assert!(dot.contains("    b21 -> computed [style=dashed];\n"));
+        assert!(dot.contains("    b24 -> b24 [style=dotted, label=\"writes 24\"];\n"));
+    }
+}
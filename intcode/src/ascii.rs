@@ -0,0 +1,98 @@
+use super::devices::Output;
+use super::{Intcode, IntcodeError};
+
+/// Queues `line` followed by a newline as input. Fails without queueing anything
+/// if the line has characters outside of ASCII.
+pub fn send_line(code: &mut Intcode, line: &str) -> Result<(), IntcodeError> {
+    if let Some(ch) = line.chars().find(|ch| !ch.is_ascii()) {
+        return Err(IntcodeError::NotAscii(ch));
+    }
+    for byte in line.bytes().chain(Some(b'\n')) {
+        code.push_input(byte as i64);
+    }
+    Ok(())
+}
+
+/// Output split into lines of text. Values that aren't ASCII, usually the
+/// final answer of a program, are kept in `answers` instead.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ascii {
+    pub lines: Vec<String>,
+    pub answers: Vec<i64>,
+    partial: String,
+}
+
+impl Ascii {
+    pub fn new() -> Ascii {
+        Ascii::default()
+    }
+
+    pub fn push(&mut self, val: i64) {
+        match val {
+            10 => self.lines.push(std::mem::take(&mut self.partial)),
+            0..=127 => self.partial.push(val as u8 as char),
+            _ => self.answers.push(val),
+        }
+    }
+
+    /// Text written since the last newline, like a prompt waiting for input.
+    pub fn partial(&self) -> &str {
+        &self.partial
+    }
+}
+
+impl Output for Ascii {
+    fn output(&mut self, val: i64) {
+        self.push(val)
+    }
+}
+
+/// Takes everything in `output` as text. An unterminated last line is kept as a line of its own.
+pub fn read_output(code: &mut Intcode) -> Ascii {
+    let mut ascii = Ascii::new();
+    for val in code.output.drain(..) {
+        ascii.push(val);
+    }
+    if !ascii.partial.is_empty() {
+        ascii.lines.push(std::mem::take(&mut ascii.partial));
+    }
+    ascii
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_output, send_line};
+    use crate::asm::assemble;
+    use crate::{run, Intcode, IntcodeError};
+
+    #[test]
+    fn lines_and_answers() {
+        // Echoes a line back, then reports its length and prints a prompt
+        let program = assemble(
+            "
+            loop:   IN [c]
+                    OUT [c]
+                    ADD [n], #1, [n]
+                    EQ [c], #10, [t]
+                    JF [t], #loop
+                    ADD [n], #999, [n]
+                    OUT [n]
+                    OUT #62
+                    HLT
+            c:      DATA 0
+            n:      DATA 0
+            t:      DATA 0
+            ",
+        )
+        .unwrap();
+        let mut code = Intcode::from_data(program);
+        assert_eq!(send_line(&mut code, "héllo"), Err(IntcodeError::NotAscii('é')));
+        assert!(code.pending_input().is_empty());
+        send_line(&mut code, "hello").unwrap();
+        run(&mut code).unwrap();
+        let ascii = read_output(&mut code);
+        assert_eq!(ascii.lines, vec!["hello", ">"]);
+        assert_eq!(ascii.answers, vec![1005]);
+        assert!(code.output.is_empty());
+    }
+}
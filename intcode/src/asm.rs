@@ -0,0 +1,364 @@
+use super::disasm::from_mnemonic;
+use super::Op;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+// Assembles the syntax printed by the disassembler, plus labels and comments:
+//
+//     ; doubles its input
+//     start:  IN [x]
+//             MUL [x], #2, [x]
+//             OUT [x]
+//             JT #1, #start
+//     x:      DATA 0
+//
+// Operands are `#value` (immediate), `[value]` (position) or `[rb+n]` (relative),
+// where a value is a number, a label or a label with an offset like `x+1`.
+// A numeric prefix such as `12:` is an address annotation and must match.
+
+/// `line` is 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmError {
+    UnknownMnemonic { line: usize, name: String },
+    WrongArity { line: usize, expected: usize, found: usize },
+    BadOperand { line: usize, text: String },
+    BadLabel { line: usize, name: String },
+    DuplicateLabel { line: usize, name: String },
+    UndefinedLabel { line: usize, name: String },
+    ImmediateWrite { line: usize, operand: usize },
+    AddressMismatch { line: usize, expected: usize, found: usize },
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AsmError::UnknownMnemonic { line, name } => {
+                write!(f, "line {}: unknown mnemonic {:?}", line, name)
+            }
+            AsmError::WrongArity { line, expected, found } => write!(
+                f,
+                "line {}: expected {} operands, found {}",
+                line, expected, found
+            ),
+            AsmError::BadOperand { line, text } => {
+                write!(f, "line {}: cannot parse operand {:?}", line, text)
+            }
+            AsmError::BadLabel { line, name } => {
+                write!(f, "line {}: {:?} is not a valid label", line, name)
+            }
+            AsmError::DuplicateLabel { line, name } => {
+                write!(f, "line {}: label {:?} is already defined", line, name)
+            }
+            AsmError::UndefinedLabel { line, name } => {
+                write!(f, "line {}: undefined label {:?}", line, name)
+            }
+            AsmError::ImmediateWrite { line, operand } => write!(
+                f,
+                "line {}: operand {} is written to and cannot be immediate",
+                line, operand
+            ),
+            AsmError::AddressMismatch { line, expected, found } => write!(
+                f,
+                "line {}: annotated address {} but assembling at {}",
+                line, expected, found
+            ),
+        }
+    }
+}
+
+impl Error for AsmError {}
+
+fn opcode(op: Op) -> i64 {
+    match op {
+        Op::Add => 1,
+        Op::Mul => 2,
+        Op::Read => 3,
+        Op::Write => 4,
+        Op::JumpIfTrue => 5,
+        Op::JumpIfFalse => 6,
+        Op::LessThan => 7,
+        Op::Equals => 8,
+        Op::AdjustRelBase => 9,
+        Op::Stop => 99,
+    }
+}
+
+#[derive(Debug)]
+enum Value {
+    Number(i64),
+    Label(String, i64),
+}
+
+#[derive(Debug)]
+enum Operand {
+    Position(Value),
+    Immediate(Value),
+    Relative(i64),
+}
+
+#[derive(Debug)]
+enum Item {
+    Instruction(Op, Vec<Operand>),
+    Data(Vec<Value>),
+}
+
+fn is_label(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+fn parse_value(text: &str) -> Option<Value> {
+    if let Ok(n) = text.parse() {
+        return Some(Value::Number(n));
+    }
+    let (name, offset) = match text.find(['+', '-']) {
+        Some(i) => (text[..i].trim(), text[i..].replace(' ', "").parse().ok()?),
+        None => (text, 0),
+    };
+    if is_label(name) && !name.eq_ignore_ascii_case("rb") {
+        Some(Value::Label(name.to_string(), offset))
+    } else {
+        None
+    }
+}
+
+fn parse_operand(line: usize, text: &str) -> Result<Operand, AsmError> {
+    let bad = || AsmError::BadOperand {
+        line,
+        text: text.to_string(),
+    };
+    if let Some(value) = text.strip_prefix('#') {
+        return parse_value(value.trim()).map(Operand::Immediate).ok_or_else(bad);
+    }
+    let inner = text
+        .strip_prefix('[')
+        .and_then(|t| t.strip_suffix(']'))
+        .ok_or_else(bad)?
+        .trim();
+    // `rb` on its own or followed by an offset, labels like `rbuf` are positions
+    let offset = match inner.get(..2) {
+        Some(rb) if rb.eq_ignore_ascii_case("rb") => inner[2..].replace(' ', ""),
+        _ => return parse_value(inner).map(Operand::Position).ok_or_else(bad),
+    };
+    if offset.is_empty() {
+        Ok(Operand::Relative(0))
+    } else if offset.starts_with('+') || offset.starts_with('-') {
+        offset.parse().map(Operand::Relative).map_err(|_| bad())
+    } else {
+        parse_value(inner).map(Operand::Position).ok_or_else(bad)
+    }
+}
+
+fn parse_item(line: usize, text: &str) -> Result<Item, AsmError> {
+    let (name, rest) = match text.find(char::is_whitespace) {
+        Some(i) => (&text[..i], text[i..].trim()),
+        None => (text, ""),
+    };
+    let operands: Vec<&str> = if rest.is_empty() {
+        vec![]
+    } else {
+        rest.split(',').map(|o| o.trim()).collect()
+    };
+    if name.eq_ignore_ascii_case("DATA") {
+        let values = operands
+            .iter()
+            .map(|o| {
+                parse_value(o).ok_or_else(|| AsmError::BadOperand {
+                    line,
+                    text: o.to_string(),
+                })
+            })
+            .collect::<Result<Vec<Value>, AsmError>>()?;
+        return Ok(Item::Data(values));
+    }
+    let op = from_mnemonic(name).ok_or_else(|| AsmError::UnknownMnemonic {
+            line,
+            name: name.to_string(),
+        })?;
+    if operands.len() != op.arity() {
+        return Err(AsmError::WrongArity {
+            line,
+            expected: op.arity(),
+            found: operands.len(),
+        });
+    }
+    let operands = operands
+        .iter()
+        .map(|o| parse_operand(line, o))
+        .collect::<Result<Vec<Operand>, AsmError>>()?;
+    if let Some(i) = op.write_arg() {
+        if let Operand::Immediate(_) = operands[i] {
+            return Err(AsmError::ImmediateWrite { line, operand: i + 1 });
+        }
+    }
+    Ok(Item::Instruction(op, operands))
+}
+
+fn resolve(line: usize, value: &Value, labels: &HashMap<String, usize>) -> Result<i64, AsmError> {
+    match value {
+        Value::Number(n) => Ok(*n),
+        Value::Label(name, offset) => match labels.get(name) {
+            Some(addr) => Ok(*addr as i64 + offset),
+            None => Err(AsmError::UndefinedLabel {
+                line,
+                name: name.clone(),
+            }),
+        },
+    }
+}
+
+/// Assembles `source` into a program for `Intcode::from_data`.
+pub fn assemble(source: &str) -> Result<Vec<i64>, AsmError> {
+    let mut labels = HashMap::new();
+    let mut items = Vec::new();
+    let mut addr = 0;
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let mut text = match text.find(';') {
+            Some(comment) => &text[..comment],
+            None => text,
+        }
+        .trim();
+        while let Some(colon) = text.find(':') {
+            let name = text[..colon].trim();
+            if let Ok(annotated) = name.parse::<usize>() {
+                if annotated != addr {
+                    return Err(AsmError::AddressMismatch {
+                        line,
+                        expected: annotated,
+                        found: addr,
+                    });
+                }
+            } else if !is_label(name) {
+                return Err(AsmError::BadLabel {
+                    line,
+                    name: name.to_string(),
+                });
+            } else if labels.insert(name.to_string(), addr).is_some() {
+                return Err(AsmError::DuplicateLabel {
+                    line,
+                    name: name.to_string(),
+                });
+            }
+            text = text[colon + 1..].trim();
+        }
+        if text.is_empty() {
+            continue;
+        }
+        let item = parse_item(line, text)?;
+        addr += match &item {
+            Item::Instruction(op, _) => op.arity() + 1,
+            Item::Data(values) => values.len(),
+        };
+        items.push((line, item));
+    }
+
+    let mut data = Vec::with_capacity(addr);
+    for (line, item) in items {
+        match item {
+            Item::Instruction(op, operands) => {
+                let mut code = opcode(op);
+                let mut shift = 100;
+                let mut args = Vec::new();
+                for operand in &operands {
+                    let (mode, value) = match operand {
+                        Operand::Position(v) => (0, resolve(line, v, &labels)?),
+                        Operand::Immediate(v) => (1, resolve(line, v, &labels)?),
+                        Operand::Relative(offset) => (2, *offset),
+                    };
+                    code += mode * shift;
+                    shift *= 10;
+                    args.push(value);
+                }
+                data.push(code);
+                data.extend(args);
+            }
+            Item::Data(values) => {
+                for value in &values {
+                    data.push(resolve(line, value, &labels)?);
+                }
+            }
+        }
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assemble, AsmError};
+    use crate::disasm::disassemble;
+    use crate::{run_with_io, Intcode, Status};
+
+    #[test]
+    fn assembles_labels_and_modes() {
+        let source = "
+            ; doubles every input until it reads a zero
+            start:  IN [x]
+                    JF [x], #end
+                    MUL [x], #2, [rb+20]
+                    OUT [rb + 20]
+                    JT #1, #start
+            end:    HLT
+            x:      DATA 0
+        ";
+        let data = assemble(source).unwrap();
+        assert_eq!(data, vec![3, 15, 1006, 15, 14, 21002, 15, 2, 20, 204, 20, 1105, 1, 0, 99, 0]);
+        let mut code = Intcode::from_data(data);
+        assert_eq!(run_with_io(&mut code, vec![3, 21, 0]), Ok(Status::Halted));
+        assert_eq!(code.output, vec![6, 42]);
+    }
+
+    #[test]
+    fn round_trips_disassembly() {
+        let data = vec![109, -1, 21101, 5, 3, 120, 204, 0, 1005, 120, 0, 99, 42, 1];
+        let listing: Vec<String> = disassemble(&Intcode::from_data(data.clone()))
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        assert_eq!(assemble(&listing.join("\n")), Ok(data));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            assemble("JT #1, #nowhere"),
+            Err(AsmError::UndefinedLabel { line: 1, name: "nowhere".to_string() })
+        );
+        assert_eq!(
+            assemble("x: DATA 0\nADD [x], #1, #3"),
+            Err(AsmError::ImmediateWrite { line: 2, operand: 3 })
+        );
+        assert_eq!(assemble("IN #3"), Err(AsmError::ImmediateWrite { line: 1, operand: 1 }));
+        assert_eq!(
+            assemble("x: HLT\nx: HLT"),
+            Err(AsmError::DuplicateLabel { line: 2, name: "x".to_string() })
+        );
+        assert_eq!(
+            assemble("JMP #0"),
+            Err(AsmError::UnknownMnemonic { line: 1, name: "JMP".to_string() })
+        );
+        assert_eq!(
+            assemble("OUT #1, #2"),
+            Err(AsmError::WrongArity { line: 1, expected: 1, found: 2 })
+        );
+        assert_eq!(
+            assemble("HLT\n3: HLT"),
+            Err(AsmError::AddressMismatch { line: 2, expected: 3, found: 1 })
+        );
+        assert_eq!(
+            assemble("OUT [€]"),
+            Err(AsmError::BadOperand { line: 1, text: "[€]".to_string() })
+        );
+        assert_eq!(
+            assemble("OUT [rb x]"),
+            Err(AsmError::BadOperand { line: 1, text: "[rb x]".to_string() })
+        );
+        assert_eq!(assemble("OUT [rbuf]\nOUT [rb-1]\nrbuf: DATA 5"), Ok(vec![4, 4, 204, -1, 5]));
+    }
+}
@@ -0,0 +1,86 @@
+use super::{run_with_io, Intcode, IntcodeError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// What a single run of a batch produced: everything the machine output before it
+/// halted or ran out of input, or the fault that stopped it.
+pub type BatchResult = Result<Vec<i64>, IntcodeError>;
+
+/// Runs a fork of `code` on every input vector, spread over all available cores.
+/// Results come back in the order of `inputs`.
+pub fn run_batch<I>(code: &Intcode, inputs: I) -> Vec<BatchResult>
+where
+    I: IntoIterator<Item = Vec<i64>>,
+{
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    run_batch_on(code, inputs, threads)
+}
+
+/// Like `run_batch`, on `threads` threads. Runs don't share anything but the
+/// program, so the results are the same whatever the number of threads.
+pub fn run_batch_on<I>(code: &Intcode, inputs: I, threads: usize) -> Vec<BatchResult>
+where
+    I: IntoIterator<Item = Vec<i64>>,
+{
+    let inputs: Vec<Vec<i64>> = inputs.into_iter().collect();
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, BatchResult)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.clamp(1, inputs.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    // Each worker takes the next run nobody has started yet
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let input = match inputs.get(i) {
+                            Some(input) => input.clone(),
+                            None => break done,
+                        };
+                        let mut machine = code.fork();
+                        let result = run_with_io(&mut machine, input).map(|_| machine.output);
+                        done.push((i, result));
+                    }
+                })
+            })
+            .collect();
+        workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+    });
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_batch, run_batch_on};
+    use crate::asm::assemble;
+    use crate::{Intcode, IntcodeError};
+
+    #[test]
+    fn results_in_input_order() {
+        // Outputs its input and the square of it, then jumps to the input if it's negative
+        let program = assemble(
+            "
+            IN [x]
+            OUT [x]
+            MUL [x], [x], [y]
+            OUT [y]
+            LT [x], #0, [neg]
+            JT [neg], [x]
+            HLT
+            x: DATA 0
+            y: DATA 0
+            neg: DATA 0
+            ",
+        )
+        .unwrap();
+        let code = Intcode::from_data(program);
+        let inputs = (-50..50).map(|x| vec![x]);
+        let results = run_batch_on(&code, inputs.clone(), 7);
+        assert_eq!(results.len(), 100);
+        assert_eq!(results[60], Ok(vec![10, 100]));
+        assert_eq!(results[0], Err(IntcodeError::NegativeAddress { pos: 14, addr: -50 }));
+        assert_eq!(results, run_batch_on(&code, inputs.clone(), 1));
+        assert_eq!(results, run_batch(&code, inputs));
+        assert!(run_batch(&code, vec![]).is_empty());
+    }
+}
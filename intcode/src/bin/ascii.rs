@@ -0,0 +1,85 @@
+use intcode::ascii::send_line;
+use intcode::devices::run_with;
+use intcode::snapshot::{load_snapshot, save_snapshot};
+use intcode::{intcode_from_file, Status};
+use std::env;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+
+const HELP: &str = "\
+Lines typed here are sent to the program. Meta commands:
+!save <file>     snapshot the machine
+!load <file>     continue from a snapshot
+!help            show this text
+!quit            leave";
+
+// Everything shown on the terminal also goes to the transcript, if there is one
+fn show(transcript: &mut Option<File>, text: &str) -> io::Result<()> {
+    print!("{}", text);
+    io::stdout().flush()?;
+    if let Some(file) = transcript {
+        file.write_all(text.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 || args.len() > 3 {
+        eprintln!("Usage: ascii <program> [transcript]");
+        std::process::exit(2);
+    }
+    let mut code = intcode_from_file(&args[1])?;
+    let mut transcript = match args.get(2) {
+        Some(file) => Some(File::create(file)?),
+        None => None,
+    };
+
+    let stdin = io::stdin();
+    loop {
+        let mut shown = String::new();
+        let status = run_with(&mut code, &mut || None, &mut |val| {
+            // Printed straight away so long running programs show progress
+            let text = match val {
+                0..=127 => (val as u8 as char).to_string(),
+                _ => format!("{}\n", val),
+            };
+            print!("{}", text);
+            shown += &text;
+        })?;
+        io::stdout().flush()?;
+        if let Some(file) = &mut transcript {
+            file.write_all(shown.as_bytes())?;
+        }
+        if status == Status::Halted {
+            break;
+        }
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim_end_matches(&['\r', '\n'][..]);
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let result = match words.as_slice() {
+            ["!save", file] => save_snapshot(&code, file).map(|_| format!("Saved to {}\n", file)),
+            ["!load", file] => load_snapshot(file).map(|loaded| {
+                code = loaded;
+                format!("Loaded {}\n", file)
+            }),
+            ["!help"] => Ok(format!("{}\n", HELP)),
+            ["!quit"] => break,
+            _ if line.starts_with('!') => Ok(format!("Unknown command {:?}, try !help\n", line)),
+            _ => send_line(&mut code, line).map(|_| String::new()),
+        };
+        if let Some(file) = &mut transcript {
+            writeln!(file, "{}", line)?;
+        }
+        match result {
+            Ok(text) => show(&mut transcript, &text)?,
+            Err(err) => show(&mut transcript, &format!("error: {}\n", err))?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use intcode::analysis::analyze;
+use intcode::intcode_from_file;
+use std::env;
+use std::io;
+
+// Prints the control flow graph as DOT, try `cfg program | dot -Tsvg > cfg.svg`
+fn main() -> io::Result<()> {
+    let file = match env::args().nth(1) {
+        Some(file) => file,
+        None => {
+            eprintln!("Usage: cfg <program>");
+            std::process::exit(2);
+        }
+    };
+    let code = intcode_from_file(file)?;
+    let analysis = analyze(&code);
+    print!("{}", analysis.to_dot(&code));
+    let computed = analysis.blocks.iter().filter(|block| block.computed_jump).count();
+    eprintln!(
+        "{} blocks, {} ending in computed jumps, {} writes into code",
+        analysis.blocks.len(),
+        computed,
+        analysis.code_writes.len()
+    );
+    Ok(())
+}
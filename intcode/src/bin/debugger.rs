@@ -0,0 +1,39 @@
+use intcode::debugger::{Debugger, HELP};
+use intcode::intcode_from_file;
+use std::env;
+use std::io;
+use std::io::prelude::*;
+
+fn main() -> io::Result<()> {
+    let file = match env::args().nth(1) {
+        Some(file) => file,
+        None => {
+            eprintln!("Usage: debugger <program>");
+            std::process::exit(2);
+        }
+    };
+    let mut dbg = Debugger::new(intcode_from_file(file)?);
+    println!("{}\n", HELP);
+    println!("{}", dbg.command("r"));
+
+    let stdin = io::stdin();
+    let mut last = String::new();
+    loop {
+        print!("(icdb) ");
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        // An empty line repeats the previous command, like gdb
+        if line.trim().is_empty() {
+            line = last.clone();
+        }
+        match line.trim() {
+            "q" | "quit" => break,
+            command => println!("{}", dbg.command(command)),
+        }
+        last = line;
+    }
+    Ok(())
+}
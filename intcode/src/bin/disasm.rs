@@ -0,0 +1,22 @@
+use intcode::disasm::disassemble;
+use intcode::intcode_from_file;
+use std::env;
+use std::io;
+use std::io::prelude::*;
+
+fn main() -> io::Result<()> {
+    let file = match env::args().nth(1) {
+        Some(file) => file,
+        None => {
+            eprintln!("Usage: disasm <program>");
+            std::process::exit(2);
+        }
+    };
+    let code = intcode_from_file(file)?;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for line in disassemble(&code) {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use intcode::intcode_from_file;
+use intcode::profile::Profiler;
+use std::env;
+use std::io;
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        eprintln!("Usage: profile <program> [input...]");
+        std::process::exit(2);
+    }
+    let mut code = intcode_from_file(&args[1])?;
+    for arg in &args[2..] {
+        match arg.parse() {
+            Ok(val) => code.push_input(val),
+            Err(_) => {
+                eprintln!("Input {:?} is not a number", arg);
+                std::process::exit(2);
+            }
+        }
+    }
+    // Reported against the program as loaded, before it had a chance to modify itself
+    let program = code.clone();
+    let mut profiler = Profiler::new();
+    let status = profiler.run(&mut code)?;
+    println!("{:?}, output {:?}\n", status, code.output);
+    print!("{}", profiler.report(&program, 20));
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use intcode::intcode_from_file;
+use intcode::trace::Tracer;
+use std::env;
+use std::io;
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        eprintln!("Usage: trace <program> <trace.jsonl> [input...]");
+        std::process::exit(2);
+    }
+    let mut code = intcode_from_file(&args[1])?;
+    for arg in &args[3..] {
+        match arg.parse() {
+            Ok(val) => code.push_input(val),
+            Err(_) => {
+                eprintln!("Input {:?} is not a number", arg);
+                std::process::exit(2);
+            }
+        }
+    }
+    let mut tracer = Tracer::new();
+    let status = tracer.run(&mut code)?;
+    tracer.save(&args[2])?;
+    println!("{:?} after {} instructions, output {:?}", status, tracer.entries.len(), code.output);
+    Ok(())
+}
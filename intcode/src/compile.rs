@@ -0,0 +1,445 @@
+use super::analysis::analyze;
+use super::disasm::{format_operation, instruction_at};
+use super::{Arg, Intcode, IntcodeError, Observer, Op, Operation, ParameterMode, Status, Step};
+
+// Generated code is a `match` on `pos` with an arm for every basic block found by
+// `analyze`. An arm runs its instructions straight through, with operand modes
+// and addresses resolved at compile time, and hands back to `Compiled::run` at the
+// end. Anything without an arm, and any arm whose code has been overwritten since,
+// is left to the interpreter one instruction at a time. Every way memory can change
+// (generated code, the interpreter, and `code_mut`) marks the machine dirty when a
+// compiled word might differ from `PROGRAM`, which makes arms check their words.
+
+/// How a compiled block hands control back to `Compiled::run`.
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    Next,
+    Output(i64),
+    Interpret,
+}
+
+/// A program translated to Rust by `compile`, with the same interface as `Intcode`.
+#[derive(Debug, Clone)]
+pub struct Compiled {
+    code: Intcode,
+    program: &'static [i64],
+    code_words: &'static [bool],
+    // Set once a compiled instruction has been overwritten
+    dirty: bool,
+    // Memory may have been changed through `code_mut` since `dirty` was last worked out
+    touched: bool,
+    exec: fn(&mut Compiled) -> Exit,
+}
+
+impl Compiled {
+    /// Called by the `new` function of a generated module.
+    pub fn new(program: &'static [i64], code_words: &'static [bool], exec: fn(&mut Compiled) -> Exit) -> Compiled {
+        Compiled {
+            code: Intcode::from_data(program.to_vec()),
+            program,
+            code_words,
+            dirty: false,
+            touched: false,
+            exec,
+        }
+    }
+
+    pub fn code(&self) -> &Intcode {
+        &self.code
+    }
+
+    /// Changes made here, like patching memory before a run, are picked up by the next `run`.
+    pub fn code_mut(&mut self) -> &mut Intcode {
+        self.touched = true;
+        &mut self.code
+    }
+
+    pub fn push_input(&mut self, value: i64) {
+        self.code.push_input(value);
+    }
+
+    pub fn status(&self) -> &Status {
+        &self.code.status
+    }
+
+    /// Like `run`: runs until the machine halts or waits for input, collecting output in `code().output`.
+    pub fn run(&mut self) -> Result<Status, IntcodeError> {
+        if let Status::Faulted(err) = &self.code.status {
+            return Err(err.clone());
+        }
+        if self.touched {
+            self.touched = false;
+            let code = &self.code;
+            let program = self.program;
+            let changed =
+                (0..program.len()).any(|addr| changes_code(program, self.code_words, addr, code.data.get(addr)));
+            self.dirty |= changed;
+        }
+        loop {
+            let step = match (self.exec)(self) {
+                Exit::Next => Step::Continue,
+                Exit::Output(val) => Step::Output(val),
+                Exit::Interpret => {
+                    let mut writes = CodeWrites {
+                        program: self.program,
+                        code_words: self.code_words,
+                        dirty: false,
+                    };
+                    let step = self.code.step_with(&mut writes)?;
+                    self.dirty |= writes.dirty;
+                    step
+                }
+            };
+            match step {
+                Step::Continue => self.code.status = Status::Running,
+                Step::Output(val) => {
+                    self.code.status = Status::Running;
+                    self.code.output.push(val);
+                }
+                Step::Halted | Step::NeedInput => break,
+            }
+        }
+        Ok(self.code.status.clone())
+    }
+
+    pub fn run_with_io(&mut self, input: Vec<i64>) -> Result<Status, IntcodeError> {
+        self.code.input.extend(input);
+        self.run()
+    }
+
+    // The rest is the runtime for generated code
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn pos(&self) -> usize {
+        self.code.pos
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn read(&self, addr: usize) -> i64 {
+        self.code.data.get(addr)
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn rel(&self, offset: i64) -> Option<usize> {
+        self.code.relative(offset).ok()
+    }
+
+    /// Returns true if the write changed a compiled instruction, which ends the block.
+    #[doc(hidden)]
+    #[inline]
+    pub fn write(&mut self, addr: usize, val: i64) -> bool {
+        self.code.data.set(addr, val);
+        if changes_code(self.program, self.code_words, addr, val) {
+            self.dirty = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn input(&mut self) -> Option<i64> {
+        self.code.input.pop_front()
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn adjust_rel_base(&mut self, val: i64) -> bool {
+        match val.checked_add(self.code.rel_base as i64) {
+            Some(base) if base >= 0 => {
+                self.code.rel_base = base as usize;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn next(&mut self, pos: usize) -> Exit {
+        self.code.pos = pos;
+        Exit::Next
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn goto(&mut self, ip: usize, target: i64) -> Exit {
+        if target < 0 {
+            self.interpret(ip)
+        } else {
+            self.next(target as usize)
+        }
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn output(&mut self, next: usize, val: i64) -> Exit {
+        self.code.pos = next;
+        Exit::Output(val)
+    }
+
+    /// Leaves the instruction at `ip` to the interpreter, which also reports any fault.
+    #[doc(hidden)]
+    #[inline]
+    pub fn interpret(&mut self, ip: usize) -> Exit {
+        self.code.pos = ip;
+        Exit::Interpret
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn stale(&self, start: usize, end: usize) -> bool {
+        self.dirty && (start..end).any(|addr| self.code.data.get(addr) != self.program[addr])
+    }
+}
+
+fn changes_code(program: &[i64], code_words: &[bool], addr: usize, val: i64) -> bool {
+    code_words.get(addr) == Some(&true) && val != program[addr]
+}
+
+// Watches instructions left to the interpreter for writes into compiled code
+struct CodeWrites {
+    program: &'static [i64],
+    code_words: &'static [bool],
+    dirty: bool,
+}
+
+impl Observer for CodeWrites {
+    fn write(&mut self, addr: usize, val: i64) {
+        self.dirty |= changes_code(self.program, self.code_words, addr, val);
+    }
+}
+
+// Negative position mode operands always fault, so they're left to the interpreter
+fn compilable(op: &Operation) -> bool {
+    op.args[..op.opcode.arity()]
+        .iter()
+        .all(|arg| !matches!(arg.mode, ParameterMode::Position) || arg.value >= 0)
+}
+
+// `let <name> = <value of arg>;`
+fn load(body: &mut String, name: &str, arg: Arg, ip: usize) {
+    *body += &match arg.mode {
+        ParameterMode::Immediate => format!("let {}: i64 = {};\n", name, arg.value),
+        ParameterMode::Position => format!("let {} = m.read({});\n", name, arg.value),
+        ParameterMode::Relative => format!(
+            "let {} = match m.rel({}) {{ Some(addr) => m.read(addr), None => return m.interpret({}) }};\n",
+            name, arg.value, ip
+        ),
+    };
+}
+
+// `let t = <address arg points at>;`
+fn target(body: &mut String, arg: Arg, ip: usize) {
+    *body += &match arg.mode {
+        ParameterMode::Relative => format!(
+            "let t = match m.rel({}) {{ Some(addr) => addr, None => return m.interpret({}) }};\n",
+            arg.value, ip
+        ),
+        _ => format!("let t = {};\n", arg.value),
+    };
+}
+
+// Appends one instruction and returns whether it always leaves the block
+fn instruction(body: &mut String, ip: usize, op: &Operation) -> bool {
+    let next = ip + op.opcode.arity() + 1;
+    let args = op.args;
+    *body += &format!("// {}: {}\n", ip, format_operation(op));
+    // Overflowing results are left to the interpreter, which faults on them
+    let result = match op.opcode {
+        Op::Add => Some(format!("match a.checked_add(b) {{ Some(val) => val, None => return m.interpret({}) }}", ip)),
+        Op::Mul => Some(format!("match a.checked_mul(b) {{ Some(val) => val, None => return m.interpret({}) }}", ip)),
+        Op::LessThan => Some("(a < b) as i64".to_string()),
+        Op::Equals => Some("(a == b) as i64".to_string()),
+        _ => None,
+    };
+    if let Some(result) = result {
+        load(body, "a", args[0], ip);
+        load(body, "b", args[1], ip);
+        target(body, args[2], ip);
+        *body += &format!("let c = {};\n", result);
+        *body += &format!("if m.write(t, c) {{\n    return m.next({});\n}}\n", next);
+        return false;
+    }
+    match op.opcode {
+        Op::Read => {
+            target(body, args[0], ip);
+            *body += &format!("let a = match m.input() {{ Some(val) => val, None => return m.interpret({}) }};\n", ip);
+            *body += &format!("if m.write(t, a) {{\n    return m.next({});\n}}\n", next);
+            false
+        }
+        Op::Write => {
+            load(body, "a", args[0], ip);
+            *body += &format!("return m.output({}, a);\n", next);
+            true
+        }
+        Op::JumpIfTrue | Op::JumpIfFalse => {
+            load(body, "a", args[0], ip);
+            let test = if op.opcode == Op::JumpIfTrue { "!=" } else { "==" };
+            *body += &format!("if a {} 0 {{\n", test);
+            let mut jump = String::new();
+            load(&mut jump, "b", args[1], ip);
+            jump += &format!("return m.goto({}, b);\n", ip);
+            *body += &indent(&jump, 1);
+            *body += "}\n";
+            false
+        }
+        Op::AdjustRelBase => {
+            load(body, "a", args[0], ip);
+            *body += &format!("if !m.adjust_rel_base(a) {{\n    return m.interpret({});\n}}\n", ip);
+            false
+        }
+        _ => {
+            *body += &format!("return m.interpret({});\n", ip);
+            true
+        }
+    }
+}
+
+fn indent(text: &str, levels: usize) -> String {
+    let pad = "    ".repeat(levels);
+    text.lines()
+        .map(|line| if line.is_empty() { "\n".to_string() } else { format!("{}{}\n", pad, line) })
+        .collect()
+}
+
+fn array<T: ToString>(values: &[T]) -> String {
+    let lines: Vec<String> = values
+        .chunks(16)
+        .map(|chunk| chunk.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(", "))
+        .collect();
+    format!("[\n{}]", indent(&lines.join(",\n"), 1))
+}
+
+/// Translates the program in `code` into the source of a Rust module whose `new()`
+/// returns a fresh `Compiled` machine. Meant to be written to `OUT_DIR` by a build
+/// script and pulled in with `include!`.
+pub fn compile(code: &Intcode) -> String {
+    let program = code.data.dense();
+    let mut code_words = vec![false; program.len()];
+    let mut arms = String::new();
+    for block in analyze(code).blocks {
+        let mut body = String::new();
+        let mut ip = block.start;
+        let mut left = false;
+        while ip < block.end {
+            let op = instruction_at(code, ip).expect("blocks hold instructions");
+            if !compilable(&op) {
+                break;
+            }
+            left = instruction(&mut body, ip, &op);
+            ip += op.opcode.arity() + 1;
+        }
+        if ip == block.start {
+            continue;
+        }
+        if !left {
+            body += &format!("return m.next({});\n", ip);
+        }
+        for word in &mut code_words[block.start..ip] {
+            *word = true;
+        }
+        arms += &format!(
+            "{} => {{\n    if m.stale({}, {}) {{\n        return Exit::Interpret;\n    }}\n{}}}\n",
+            block.start,
+            block.start,
+            ip,
+            indent(&body, 1)
+        );
+    }
+
+    format!(
+        "// Generated by intcode::compile::compile, do not edit.
+
+use intcode::compile::{{Compiled, Exit}};
+
+static PROGRAM: [i64; {len}] = {program};
+
+// Words that belong to compiled instructions
+static CODE_WORDS: [bool; {len}] = {code_words};
+
+pub fn new() -> Compiled {{
+    Compiled::new(&PROGRAM, &CODE_WORDS, exec)
+}}
+
+#[allow(unused_variables, clippy::all)]
+fn exec(m: &mut Compiled) -> Exit {{
+    match m.pos() {{
+{arms}        _ => Exit::Interpret,
+    }}
+}}
+",
+        len = program.len(),
+        program = array(&program),
+        code_words = array(&code_words),
+        arms = indent(&arms, 2)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compile;
+    use crate::asm::assemble;
+    use crate::{run, Intcode, IntcodeError, Status};
+
+    // Generated from PROGRAM below; the test fails with the new source if they drift apart
+    mod countdown {
+        include!("../testdata/countdown.rs");
+    }
+
+    const PROGRAM: &str = "
+            ARB #stack
+            IN [rb+0]
+    loop:   OUT [rb+0]
+            ADD [rb+0], #-1, [rb+0]
+            JT [rb+0], #loop
+            ; turns the ADD below into a MUL just before it runs
+            ADD #1102, #0, [patch]
+    patch:  ADD #6, #7, [result]
+            OUT [result]
+            HLT
+    result: DATA 0
+    stack:  DATA 0
+    ";
+
+    #[test]
+    fn compiles_like_interpreting() {
+        let code = Intcode::from_data(assemble(PROGRAM).unwrap());
+        let source = compile(&code);
+        assert_eq!(source, include_str!("../testdata/countdown.rs"), "\n{}", source);
+
+        let mut compiled = countdown::new();
+        assert_eq!(compiled.run(), Ok(Status::AwaitingInput));
+        assert_eq!(compiled.run_with_io(vec![3]), Ok(Status::Halted));
+        let mut interpreted = code.clone();
+        run(&mut interpreted).unwrap();
+        crate::run_with_io(&mut interpreted, vec![3]).unwrap();
+        assert_eq!(compiled.code().output, vec![3, 2, 1, 42]);
+        assert_eq!(compiled.code().output, interpreted.output);
+        assert_eq!(compiled.code().data, interpreted.data);
+        assert_eq!(compiled.code().rel_base(), interpreted.rel_base());
+
+        // Counting down from the smallest value overflows the same way in both
+        let overflow = Err(IntcodeError::ValueOverflow { pos: 6 });
+        assert_eq!(countdown::new().run_with_io(vec![i64::MIN]), overflow);
+        assert_eq!(crate::run_with_io(&mut code.clone(), vec![i64::MIN]), overflow);
+    }
+
+    #[test]
+    fn sees_patches_made_before_running() {
+        // Counts down by 2 instead of 1
+        let mut compiled = countdown::new();
+        compiled.code_mut().data[8] = -2;
+        let mut interpreted = Intcode::from_data(assemble(PROGRAM).unwrap());
+        interpreted.data[8] = -2;
+        assert_eq!(compiled.run_with_io(vec![4]), Ok(Status::Halted));
+        crate::run_with_io(&mut interpreted, vec![4]).unwrap();
+        assert_eq!(compiled.code().output, vec![4, 2, 42]);
+        assert_eq!(compiled.code().output, interpreted.output);
+    }
+}
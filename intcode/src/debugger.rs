@@ -0,0 +1,321 @@
+use super::disasm::{disassemble_at, from_mnemonic, mnemonic};
+use super::history::History;
+use super::{Intcode, IntcodeError, Op, Step};
+use std::collections::BTreeSet;
+
+const MAX_LINES: usize = 10_000;
+
+pub const HELP: &str = "\
+s [n]            step n instructions (default 1)
+c                continue until a breakpoint, input is needed or the program halts
+rs [n]           step back n instructions (default 1)
+rw <addr>        go back to just before the last write to a memory cell
+rewind <cycle>   go back to an earlier cycle
+b <addr|OP>      break at an address or on an opcode mnemonic like OUT
+d <addr|OP>      delete a breakpoint
+bl               list breakpoints
+r                show cycle, pos, rel_base, status and the current instruction
+l [addr] [n]     disassemble n instructions (default: 10 from pos)
+x <addr> [n]     show n memory cells (default 1)
+set <addr> <val> write a memory cell
+in <val>...      queue input values
+out              show and clear output produced so far
+q                quit";
+
+/// Why `cont` gave control back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stop {
+    Breakpoint(usize),
+    OpBreakpoint(usize),
+    AwaitingInput,
+    Halted,
+    Faulted(IntcodeError),
+}
+
+/// Drives an `Intcode` one command at a time; the REPL itself lives in the `debugger` binary.
+/// Every instruction is recorded, so it can step backwards too. Changes made with
+/// `set` and `in` are not undone by going back.
+pub struct Debugger {
+    code: Intcode,
+    history: History,
+    breakpoints: BTreeSet<usize>,
+    op_breakpoints: Vec<Op>,
+}
+
+impl Debugger {
+    pub fn new(code: Intcode) -> Debugger {
+        Debugger {
+            code,
+            history: History::new(),
+            breakpoints: BTreeSet::new(),
+            op_breakpoints: Vec::new(),
+        }
+    }
+
+    pub fn code(&self) -> &Intcode {
+        &self.code
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Steps once, keeping output in `output` until it's looked at.
+    pub fn step(&mut self) -> Result<Step, IntcodeError> {
+        let step = self.history.step(&mut self.code)?;
+        if let Step::Output(val) = step {
+            self.code.output.push(val);
+        }
+        Ok(step)
+    }
+
+    /// Goes back to `cycle`, taking back output that hasn't been looked at yet.
+    pub fn rewind(&mut self, cycle: usize) {
+        for delta in self.history.rewind(&mut self.code, cycle) {
+            // `out` clears everything at once, so whatever is left ends with the
+            // latest output, unless that was cleared already
+            if delta.output.is_some() {
+                self.code.output.pop();
+            }
+        }
+    }
+
+    fn at_op_breakpoint(&self) -> bool {
+        match self.code.op() {
+            Ok(op) => self.op_breakpoints.contains(&op.opcode),
+            Err(_) => false,
+        }
+    }
+
+    /// Always executes at least one instruction, so continuing from a breakpoint moves on.
+    pub fn cont(&mut self) -> Stop {
+        loop {
+            match self.step() {
+                Ok(Step::Continue) | Ok(Step::Output(_)) => {}
+                Ok(Step::NeedInput) => return Stop::AwaitingInput,
+                Ok(Step::Halted) => return Stop::Halted,
+                Err(err) => return Stop::Faulted(err),
+            }
+            let pos = self.code.pos;
+            if self.breakpoints.contains(&pos) {
+                return Stop::Breakpoint(pos);
+            }
+            if self.at_op_breakpoint() {
+                return Stop::OpBreakpoint(pos);
+            }
+        }
+    }
+
+    fn location(&self) -> String {
+        disassemble_at(&self.code, self.code.pos).to_string()
+    }
+
+    /// Runs one REPL command and returns what should be printed.
+    pub fn command(&mut self, line: &str) -> String {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let result = match words.as_slice() {
+            [] => Ok(String::new()),
+            ["h"] | ["help"] => Ok(HELP.to_string()),
+            ["s"] | ["step"] => self.step_n(1),
+            ["s", n] | ["step", n] => parse_count(n).and_then(|n| self.step_n(n)),
+            ["c"] | ["continue"] => {
+                let stop = self.cont();
+                Ok(format!("{:?}\n{}", stop, self.location()))
+            }
+            ["rs"] => self.step_back(1),
+            ["rs", n] => parse_count(n).and_then(|n| self.step_back(n)),
+            ["rw", addr] => parse_addr(addr).and_then(|a| match self.history.last_write(a) {
+                Some(cycle) => {
+                    self.rewind(cycle);
+                    Ok(format!("cycle {}\n{}", cycle, self.location()))
+                }
+                None => Err(format!("nothing has written to {} yet", a)),
+            }),
+            ["rewind", cycle] => parse_count(cycle).and_then(|c| {
+                if c > self.history.cycle() {
+                    return Err(format!("cycle {} hasn't happened yet", c));
+                }
+                self.rewind(c);
+                Ok(format!("cycle {}\n{}", c, self.location()))
+            }),
+            ["b", target] | ["break", target] => self.set_breakpoint(target, true),
+            ["d", target] | ["delete", target] => self.set_breakpoint(target, false),
+            ["bl"] => Ok(self.list_breakpoints()),
+            ["r"] | ["regs"] => Ok(format!(
+                "cycle {}  pos {}  rel_base {}  status {:?}  input {:?}\n{}",
+                self.history.cycle(),
+                self.code.pos,
+                self.code.rel_base,
+                self.code.status,
+                self.code.input,
+                self.location()
+            )),
+            ["l"] | ["list"] => Ok(self.list(self.code.pos, 10)),
+            ["l", addr] | ["list", addr] => parse_addr(addr).map(|a| self.list(a, 10)),
+            ["l", addr, n] | ["list", addr, n] => {
+                parse_addr(addr).and_then(|a| parse_lines(n).map(|n| self.list(a, n)))
+            }
+            ["x", addr] => parse_addr(addr).map(|a| self.examine(a, 1)),
+            ["x", addr, n] => parse_addr(addr).and_then(|a| parse_lines(n).map(|n| self.examine(a, n))),
+            ["set", addr, val] => parse_addr(addr).and_then(|a| {
+                parse_num(val).map(|v| {
+                    self.code.poke(a, v);
+                    self.examine(a, 1)
+                })
+            }),
+            ["in", vals @ ..] if !vals.is_empty() => vals
+                .iter()
+                .map(|v| parse_num(v))
+                .collect::<Result<Vec<i64>, String>>()
+                .map(|vals| {
+                    self.code.input.extend(vals);
+                    format!("input {:?}", self.code.input)
+                }),
+            ["out"] => Ok(format!("{:?}", self.code.output.drain(..).collect::<Vec<i64>>())),
+            _ => Err(format!("Unknown command {:?}, try h", line.trim())),
+        };
+        match result {
+            Ok(text) => text,
+            Err(err) => format!("error: {}", err),
+        }
+    }
+
+    fn step_n(&mut self, n: usize) -> Result<String, String> {
+        for _ in 0..n {
+            match self.step() {
+                Ok(Step::Continue) | Ok(Step::Output(_)) => {}
+                Ok(step) => return Ok(format!("{:?}\n{}", step, self.location())),
+                Err(err) => return Err(err.to_string()),
+            }
+        }
+        Ok(self.location())
+    }
+
+    fn step_back(&mut self, n: usize) -> Result<String, String> {
+        let cycle = self.history.cycle().saturating_sub(n);
+        self.rewind(cycle);
+        Ok(format!("cycle {}\n{}", cycle, self.location()))
+    }
+
+    fn set_breakpoint(&mut self, target: &str, enable: bool) -> Result<String, String> {
+        if let Some(op) = from_mnemonic(target) {
+            self.op_breakpoints.retain(|o| *o != op);
+            if enable {
+                self.op_breakpoints.push(op);
+            }
+        } else {
+            let addr = parse_addr(target)?;
+            if enable {
+                self.breakpoints.insert(addr);
+            } else {
+                self.breakpoints.remove(&addr);
+            }
+        }
+        Ok(self.list_breakpoints())
+    }
+
+    fn list_breakpoints(&self) -> String {
+        let mut targets: Vec<String> = self.breakpoints.iter().map(|b| b.to_string()).collect();
+        targets.extend(self.op_breakpoints.iter().map(|op| mnemonic(*op).to_string()));
+        format!("breakpoints: {}", targets.join(" "))
+    }
+
+    fn list(&self, mut addr: usize, n: usize) -> String {
+        let mut lines = Vec::new();
+        for _ in 0..n {
+            let line = disassemble_at(&self.code, addr);
+            let marker = if addr == self.code.pos { "=>" } else { "  " };
+            addr += line.len;
+            lines.push(format!("{}{}", marker, line));
+        }
+        lines.join("\n")
+    }
+
+    fn examine(&self, addr: usize, n: usize) -> String {
+        (addr..addr.saturating_add(n))
+            .map(|a| format!("[{}] = {}", a, self.code.peek(a)))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+fn parse_num(text: &str) -> Result<i64, String> {
+    text.parse().map_err(|_| format!("{:?} is not a number", text))
+}
+
+// Anything the machine itself could address, so 0 up to i64::MAX
+fn parse_addr(text: &str) -> Result<usize, String> {
+    match parse_num(text)? {
+        addr if addr < 0 => Err(format!("{} is not an address", addr)),
+        addr => Ok(addr as usize),
+    }
+}
+
+fn parse_count(text: &str) -> Result<usize, String> {
+    text.parse().map_err(|_| format!("{:?} is not a count", text))
+}
+
+// A count of lines to print, small enough to keep the output readable
+fn parse_lines(text: &str) -> Result<usize, String> {
+    match parse_count(text)? {
+        n if n > MAX_LINES => Err(format!("can show at most {} lines at a time", MAX_LINES)),
+        n => Ok(n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Debugger, Stop};
+    use crate::asm::assemble;
+    use crate::Intcode;
+
+    #[test]
+    fn breakpoints_and_io() {
+        let program = assemble(
+            "
+            loop:   IN [x]
+                    OUT [x]
+                    JT [x], #loop
+                    HLT
+            x:      DATA 0
+            ",
+        )
+        .unwrap();
+        let mut dbg = Debugger::new(Intcode::from_data(program));
+        assert_eq!(dbg.command("b 4"), "breakpoints: 4");
+        assert_eq!(dbg.command("b out"), "breakpoints: 4 OUT");
+        assert_eq!(dbg.cont(), Stop::AwaitingInput);
+        dbg.command("in 7 0");
+        assert_eq!(dbg.cont(), Stop::OpBreakpoint(2));
+        assert_eq!(dbg.cont(), Stop::Breakpoint(4));
+        assert_eq!(dbg.command("out"), "[7]");
+        dbg.command("d OUT");
+        dbg.command("d 4");
+        assert_eq!(dbg.command("x 8"), "[8] = 7");
+        assert_eq!(dbg.command("set 8 0"), "[8] = 0");
+        assert_eq!(dbg.command("s"), "    7: HLT");
+        assert_eq!(dbg.cont(), Stop::Halted);
+        assert_eq!(dbg.code().output, vec![]);
+
+        // `set` isn't undone, so the last write to 8 is the IN that read 7
+        assert_eq!(dbg.command("rs 2"), "cycle 2\n    4: JT [8], #0");
+        assert_eq!(dbg.command("rw 8"), "cycle 0\n    0: IN [8]");
+        assert_eq!(dbg.command("x 8"), "[8] = 0");
+        assert_eq!(dbg.code().pending_input(), &[7, 0]);
+        assert_eq!(dbg.cont(), Stop::Halted);
+        assert_eq!(dbg.command("rs 3"), "cycle 4\n    2: OUT [8]");
+        assert_eq!(dbg.command("out"), "[7]");
+        assert_eq!(dbg.command("rewind 9"), "error: cycle 9 hasn't happened yet");
+        assert_eq!(dbg.command("rewind 0"), "cycle 0\n    0: IN [8]");
+        assert_eq!(dbg.command("x -1"), "error: -1 is not an address");
+        assert_eq!(dbg.command("x 0 -1"), "error: \"-1\" is not a count");
+        assert_eq!(dbg.command("l 0 -1"), "error: \"-1\" is not a count");
+        assert_eq!(dbg.command("s -1"), "error: \"-1\" is not a count");
+        assert_eq!(dbg.command("x 0 1000000000000"), "error: can show at most 10000 lines at a time");
+        assert_eq!(dbg.command("l 0 10001"), "error: can show at most 10000 lines at a time");
+        assert_eq!(dbg.command("x 0 10000").lines().count(), 10_000);
+        assert_eq!(dbg.command("set -1 5"), "error: -1 is not an address");
+        assert_eq!(dbg.command("x 9223372036854775807 2"), "[9223372036854775807] = 0\n[9223372036854775808] = 0");
+        assert_eq!(dbg.command("rw 8"), "error: nothing has written to 8 yet");
+    }
+}
@@ -0,0 +1,222 @@
+use super::{Intcode, IntcodeError, Status, Step};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+use std::sync::mpsc::{Receiver, Sender};
+
+/// Where a machine gets its input from when its queue runs dry.
+/// `None` means there is nothing to read for now, and the machine waits.
+pub trait Input {
+    fn next_input(&mut self) -> Option<i64>;
+}
+
+/// Where a machine's output goes, one value at a time, as soon as it's written.
+pub trait Output {
+    fn output(&mut self, val: i64);
+}
+
+impl<F: FnMut() -> Option<i64>> Input for F {
+    fn next_input(&mut self) -> Option<i64> {
+        self()
+    }
+}
+
+impl Input for VecDeque<i64> {
+    fn next_input(&mut self) -> Option<i64> {
+        self.pop_front()
+    }
+}
+
+/// Blocks until a value arrives; a closed channel counts as end of input.
+impl Input for Receiver<i64> {
+    fn next_input(&mut self) -> Option<i64> {
+        self.recv().ok()
+    }
+}
+
+/// Reads whitespace or comma separated numbers from stdin, a line at a time.
+/// Words that aren't numbers are skipped. Input ends at the end of stdin, or when
+/// reading it fails, in which case the error is kept in `error`.
+#[derive(Debug, Default)]
+pub struct Stdin {
+    pending: VecDeque<i64>,
+    pub error: Option<io::Error>,
+}
+
+impl Stdin {
+    pub fn new() -> Stdin {
+        Stdin::default()
+    }
+}
+
+// The numbers in a line of input, skipping anything else
+fn numbers(line: &str) -> impl Iterator<Item = i64> + '_ {
+    line.split(|c: char| c == ',' || c.is_whitespace()).filter_map(|word| word.parse().ok())
+}
+
+impl Input for Stdin {
+    fn next_input(&mut self) -> Option<i64> {
+        while self.pending.is_empty() {
+            let mut line = String::new();
+            match io::stdin().read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) => self.pending.extend(numbers(&line)),
+                Err(err) => {
+                    self.error = Some(err);
+                    return None;
+                }
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+/// Passes input through from another source, keeping a log of it that
+/// `load_log` can replay later.
+#[derive(Debug)]
+pub struct Recorder<I> {
+    inner: I,
+    pub log: Vec<i64>,
+}
+
+impl<I: Input> Recorder<I> {
+    pub fn new(inner: I) -> Recorder<I> {
+        Recorder { inner, log: Vec::new() }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, file: P) -> io::Result<()> {
+        let mut out = io::BufWriter::new(File::create(file)?);
+        for val in &self.log {
+            writeln!(out, "{}", val)?;
+        }
+        Ok(())
+    }
+}
+
+impl<I: Input> Input for Recorder<I> {
+    fn next_input(&mut self) -> Option<i64> {
+        let val = self.inner.next_input()?;
+        self.log.push(val);
+        Some(val)
+    }
+}
+
+/// Reads a log written by `Recorder::save` back as an input source.
+pub fn load_log<P: AsRef<Path>>(file: P) -> Result<VecDeque<i64>, IntcodeError> {
+    let text = std::fs::read_to_string(file)?;
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            line.trim()
+                .parse()
+                .map_err(|_| IntcodeError::Io(format!("bad value {:?} on line {} of the input log", line, i + 1)))
+        })
+        .collect()
+}
+
+impl<F: FnMut(i64)> Output for F {
+    fn output(&mut self, val: i64) {
+        self(val)
+    }
+}
+
+impl Output for Vec<i64> {
+    fn output(&mut self, val: i64) {
+        self.push(val)
+    }
+}
+
+/// Values sent after the receiver hung up are dropped.
+impl Output for Sender<i64> {
+    fn output(&mut self, val: i64) {
+        let _ = self.send(val);
+    }
+}
+
+/// Like `run`, but pulls input from `input` whenever the queue is empty and hands every
+/// output to `output` instead of collecting it. Stops once `input` has nothing to give.
+pub fn run_with<I, O>(code: &mut Intcode, input: &mut I, output: &mut O) -> Result<Status, IntcodeError>
+where
+    I: Input + ?Sized,
+    O: Output + ?Sized,
+{
+    loop {
+        match code.step()? {
+            Step::Continue => {}
+            Step::Output(val) => output.output(val),
+            Step::Halted => break,
+            Step::NeedInput => match input.next_input() {
+                Some(val) => code.push_input(val),
+                None => break,
+            },
+        }
+    }
+    Ok(code.status.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_log, numbers, run_with, Recorder};
+    use crate::asm::assemble;
+    use crate::{Intcode, Status};
+    use std::sync::mpsc::channel;
+    use std::thread;
+
+    fn doubler() -> Intcode {
+        Intcode::from_data(
+            assemble(
+                "
+                loop:   IN [x]
+                        MUL [x], #2, [x]
+                        OUT [x]
+                        JT #1, #loop
+                x:      DATA 0
+                ",
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn pulls_and_pushes_values() {
+        let mut code = doubler();
+        let mut next = 0;
+        let mut seen = Vec::new();
+        let mut input = Recorder::new(|| {
+            next += 1;
+            if next <= 3 { Some(next) } else { None }
+        });
+        let status = run_with(&mut code, &mut input, &mut |val| seen.push(val)).unwrap();
+        assert_eq!(status, Status::AwaitingInput);
+        assert_eq!(seen, vec![2, 4, 6]);
+        assert!(code.output.is_empty());
+
+        let log = std::env::temp_dir().join(format!("intcode-input-log-{}", std::process::id()));
+        input.save(&log).unwrap();
+        let mut replay = load_log(&log).unwrap();
+        std::fs::remove_file(&log).unwrap();
+        let mut out = Vec::new();
+        run_with(&mut doubler(), &mut replay, &mut out).unwrap();
+        assert_eq!(out, seen);
+    }
+
+    #[test]
+    fn skips_words_that_are_not_numbers() {
+        assert_eq!(numbers("1, -2,x 3\tfour,,5\n").collect::<Vec<_>>(), vec![1, -2, 3, 5]);
+    }
+
+    #[test]
+    fn channels() {
+        let (to_machine, mut input) = channel();
+        let (mut output, from_machine) = channel();
+        let worker = thread::spawn(move || run_with(&mut doubler(), &mut input, &mut output));
+        for val in 1..=3 {
+            to_machine.send(val).unwrap();
+            assert_eq!(from_machine.recv(), Ok(val * 2));
+        }
+        drop(to_machine);
+        assert_eq!(worker.join().unwrap(), Ok(Status::AwaitingInput));
+    }
+}
@@ -0,0 +1,141 @@
+use super::{Arg, Intcode, Op, Operation, ParameterMode};
+use std::fmt;
+
+/// One line of a listing: an instruction, or a single word that doesn't decode as one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub addr: usize,
+    pub len: usize,
+    pub text: String,
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>5}: {}", self.addr, self.text)
+    }
+}
+
+pub(crate) fn mnemonic(op: Op) -> &'static str {
+    match op {
+        Op::Add => "ADD",
+        Op::Mul => "MUL",
+        Op::Read => "IN",
+        Op::Write => "OUT",
+        Op::JumpIfTrue => "JT",
+        Op::JumpIfFalse => "JF",
+        Op::LessThan => "LT",
+        Op::Equals => "EQ",
+        Op::AdjustRelBase => "ARB",
+        Op::Stop => "HLT",
+    }
+}
+
+const OPS: [Op; 10] = [
+    Op::Add,
+    Op::Mul,
+    Op::Read,
+    Op::Write,
+    Op::JumpIfTrue,
+    Op::JumpIfFalse,
+    Op::LessThan,
+    Op::Equals,
+    Op::AdjustRelBase,
+    Op::Stop,
+];
+
+pub(crate) fn from_mnemonic(name: &str) -> Option<Op> {
+    OPS.iter().cloned().find(|op| mnemonic(*op).eq_ignore_ascii_case(name))
+}
+
+fn operand(arg: Arg) -> String {
+    match arg.mode {
+        ParameterMode::Position => format!("[{}]", arg.value),
+        ParameterMode::Immediate => format!("#{}", arg.value),
+        ParameterMode::Relative if arg.value == 0 => "[rb]".to_string(),
+        ParameterMode::Relative => format!("[rb{:+}]", arg.value),
+    }
+}
+
+pub(crate) fn format_operation(op: &Operation) -> String {
+    let args: Vec<String> = op.args[..op.opcode.arity()]
+        .iter()
+        .map(|arg| operand(*arg))
+        .collect();
+    if args.is_empty() {
+        mnemonic(op.opcode).to_string()
+    } else {
+        format!("{} {}", mnemonic(op.opcode), args.join(", "))
+    }
+}
+
+// Instructions that would fault on an immediate write are data as well, and so
+// are words with stray mode digits (like 99999) that the assembler can't reproduce
+fn is_canonical(op: &Operation, word: i64) -> bool {
+    let writable = match op.opcode.write_arg() {
+        Some(i) => !matches!(op.args[i].mode, ParameterMode::Immediate),
+        None => true,
+    };
+    writable && word >= 0 && word / 10i64.pow(op.opcode.arity() as u32 + 2) == 0
+}
+
+// The instruction at `addr`, if the words there read as one
+pub(crate) fn instruction_at(code: &Intcode, addr: usize) -> Option<Operation> {
+    match code.decode(addr) {
+        Ok(op) if addr + op.opcode.arity() < code.data.len() && is_canonical(&op, code.deref(addr)) => Some(op),
+        _ => None,
+    }
+}
+
+/// Decodes the word at `addr`, falling back to a one word `DATA` line.
+pub fn disassemble_at(code: &Intcode, addr: usize) -> Line {
+    match instruction_at(code, addr) {
+        Some(op) => Line {
+            addr,
+            len: op.opcode.arity() + 1,
+            text: format_operation(&op),
+        },
+        None => Line {
+            addr,
+            len: 1,
+            text: format!("DATA {}", code.deref(addr)),
+        },
+    }
+}
+
+/// Linear sweep over all of `data`.
+pub fn disassemble(code: &Intcode) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut addr = 0;
+    while addr < code.data.len() {
+        let line = disassemble_at(code, addr);
+        addr += line.len;
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::disassemble;
+    use crate::Intcode;
+
+    #[test]
+    fn listing() {
+        let code = Intcode::from_data(vec![109, -1, 21101, 5, 3, 120, 204, 0, 1005, 120, 0, 99, 42, 103, 1, 99999]);
+        let listing: Vec<String> = disassemble(&code).iter().map(|l| l.to_string()).collect();
+        assert_eq!(
+            listing,
+            vec![
+                "    0: ARB #-1",
+                "    2: ADD #5, #3, [rb+120]",
+                "    6: OUT [rb]",
+                "    8: JT [120], #0",
+                "   11: HLT",
+                "   12: DATA 42",
+                "   13: DATA 103",
+                "   14: DATA 1",
+                "   15: DATA 99999",
+            ]
+        );
+    }
+}
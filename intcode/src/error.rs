@@ -0,0 +1,74 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong while loading or running an Intcode program.
+/// `pos` is always the address of the instruction that failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntcodeError {
+    BadOpcode { pos: usize, code: i64 },
+    BadMode { pos: usize, mode: i64 },
+    IllegalWrite { pos: usize },
+    NegativeAddress { pos: usize, addr: i64 },
+    NegativeRelBase { pos: usize, base: i64 },
+    AddressOverflow { pos: usize },
+    /// An addition or multiplication whose result doesn't fit in an i64.
+    ValueOverflow { pos: usize },
+    InfiniteLoop { pos: usize },
+    Parse { offset: usize, text: String },
+    NotAscii(char),
+    Snapshot(String),
+    Io(String),
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntcodeError::BadOpcode { pos, code } => {
+                write!(f, "unknown opcode {} at {}", code, pos)
+            }
+            IntcodeError::BadMode { pos, mode } => {
+                write!(f, "unknown parameter mode {} at {}", mode, pos)
+            }
+            IntcodeError::IllegalWrite { pos } => {
+                write!(f, "write to an immediate parameter at {}", pos)
+            }
+            IntcodeError::NegativeAddress { pos, addr } => {
+                write!(f, "access to negative address {} at {}", addr, pos)
+            }
+            IntcodeError::NegativeRelBase { pos, base } => {
+                write!(f, "relative base set to negative {} at {}", base, pos)
+            }
+            IntcodeError::AddressOverflow { pos } => {
+                write!(f, "address out of range at {}", pos)
+            }
+            IntcodeError::ValueOverflow { pos } => {
+                write!(f, "arithmetic overflow at {}", pos)
+            }
+            IntcodeError::InfiniteLoop { pos } => {
+                write!(f, "stuck in an infinite loop at {}", pos)
+            }
+            IntcodeError::Parse { offset, text } => {
+                write!(f, "cannot parse {:?} as an integer at byte {}", text, offset)
+            }
+            IntcodeError::NotAscii(ch) => write!(f, "{:?} cannot be sent as ASCII", ch),
+            IntcodeError::Snapshot(message) => write!(f, "invalid snapshot: {}", message),
+            IntcodeError::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for IntcodeError {}
+
+impl From<io::Error> for IntcodeError {
+    fn from(err: io::Error) -> IntcodeError {
+        IntcodeError::Io(err.to_string())
+    }
+}
+
+// Lets the day binaries keep using `io::Result` in `main`
+impl From<IntcodeError> for io::Error {
+    fn from(err: IntcodeError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
@@ -0,0 +1,184 @@
+use super::{Intcode, IntcodeError, Op, ParameterMode, Status, Step};
+
+/// What one instruction changed, with enough of the state before it to undo it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delta {
+    pub pos: usize,
+    pub rel_base: usize,
+    pub status: Status,
+    /// The address written and the value it held before.
+    pub write: Option<(usize, i64)>,
+    pub input: Option<i64>,
+    pub output: Option<i64>,
+}
+
+/// Records a `Delta` for every instruction executed through it, so the machine
+/// can be taken back to any earlier cycle. An instruction that faults counts as a
+/// cycle too, so the fault can be stepped back over. Everything the machine does
+/// is kept, so memory use grows with the number of instructions run.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    deltas: Vec<Delta>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History::default()
+    }
+
+    /// Instructions recorded, which is also the number of the current cycle.
+    pub fn cycle(&self) -> usize {
+        self.deltas.len()
+    }
+
+    pub fn deltas(&self) -> &[Delta] {
+        &self.deltas
+    }
+
+    /// Like `Intcode::step`, recording what the instruction changed.
+    pub fn step(&mut self, code: &mut Intcode) -> Result<Step, IntcodeError> {
+        let op = code.op().ok();
+        let target = op.as_ref().and_then(|op| op.opcode.write_arg().map(|i| op.args[i])).and_then(|arg| {
+            match arg.mode {
+                ParameterMode::Position => Some(arg.value),
+                ParameterMode::Relative => arg.value.checked_add(code.rel_base as i64),
+                ParameterMode::Immediate => None,
+            }
+        });
+        let reads = matches!(&op, Some(op) if op.opcode == Op::Read);
+        let mut delta = Delta {
+            pos: code.pos,
+            rel_base: code.rel_base,
+            status: code.status.clone(),
+            // Negative targets fault before anything is written
+            write: target.filter(|t| *t >= 0).map(|t| (t as usize, code.peek(t as usize))),
+            input: if reads { code.input.front().cloned() } else { None },
+            output: None,
+        };
+
+        let step = match code.step() {
+            Ok(step) => step,
+            Err(err) => {
+                // A fault changes nothing but the status, so stepping back over it
+                // leaves the machine ready to run the instruction again
+                if !matches!(delta.status, Status::Faulted(_)) {
+                    delta.write = None;
+                    delta.input = None;
+                    self.deltas.push(delta);
+                }
+                return Err(err);
+            }
+        };
+        match step {
+            // Neither waiting for input nor stepping a halted machine changes anything
+            Step::NeedInput => return Ok(step),
+            Step::Halted if delta.status == Status::Halted => return Ok(step),
+            Step::Output(val) => delta.output = Some(val),
+            _ => {}
+        }
+        self.deltas.push(delta);
+        Ok(step)
+    }
+
+    /// Undoes the last recorded instruction. Output it produced is left to the caller,
+    /// as `step` never collects it.
+    pub fn step_back(&mut self, code: &mut Intcode) -> Option<Delta> {
+        let delta = self.deltas.pop()?;
+        if let Some((addr, old)) = delta.write {
+            code.poke(addr, old);
+        }
+        if let Some(val) = delta.input {
+            code.input.push_front(val);
+        }
+        code.pos = delta.pos;
+        code.rel_base = delta.rel_base;
+        code.status = delta.status.clone();
+        Some(delta)
+    }
+
+    /// Steps back until `cycle` is the current cycle, returning what was undone, latest first.
+    pub fn rewind(&mut self, code: &mut Intcode, cycle: usize) -> Vec<Delta> {
+        let mut undone = Vec::new();
+        while self.cycle() > cycle {
+            undone.extend(self.step_back(code));
+        }
+        undone
+    }
+
+    /// The cycle of the last recorded instruction that wrote to `addr`.
+    pub fn last_write(&self, addr: usize) -> Option<usize> {
+        self.deltas.iter().rposition(|delta| matches!(delta.write, Some((a, _)) if a == addr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::History;
+    use crate::asm::assemble;
+    use crate::{Intcode, Status, Step};
+
+    #[test]
+    fn steps_back_to_any_cycle() {
+        let program = assemble(
+            "
+                    IN [x]
+            loop:   ADD [x], #-1, [x]
+                    ARB #1
+                    OUT [x]
+                    JT [x], #loop
+                    HLT
+            x:      DATA 0
+            ",
+        )
+        .unwrap();
+        let start = Intcode::from_data(program);
+        let mut code = start.clone();
+        code.push_input(3);
+        let mut history = History::new();
+        let mut output = Vec::new();
+        loop {
+            match history.step(&mut code).unwrap() {
+                Step::Output(val) => output.push(val),
+                Step::Halted => break,
+                _ => {}
+            }
+        }
+        assert_eq!(output, vec![2, 1, 0]);
+        assert_eq!(history.cycle(), 14);
+        assert_eq!(history.last_write(14), Some(9));
+
+        let undone = history.rewind(&mut code, 10);
+        assert_eq!(undone.len(), 4);
+        assert_eq!(undone[2].output, Some(0));
+        assert_eq!(code.peek(14), 0);
+        assert_eq!(code.pos(), 6);
+        assert_eq!(history.step(&mut code), Ok(Step::Continue));
+
+        history.rewind(&mut code, 0);
+        assert_eq!(code.data, start.data);
+        assert_eq!((code.pos(), code.rel_base()), (0, 0));
+        assert_eq!(code.pending_input().iter().collect::<Vec<_>>(), vec![&3]);
+        assert_eq!(code.status(), &Status::Running);
+        assert_eq!(history.step_back(&mut code), None);
+
+        // Stepping back over a fault clears it, even on the first instruction
+        let mut code = Intcode::from_data(vec![104, 5, 4, -3, 99]);
+        let mut history = History::new();
+        assert_eq!(history.step(&mut code), Ok(Step::Output(5)));
+        assert!(history.step(&mut code).is_err());
+        assert!(history.step(&mut code).is_err());
+        assert_eq!(history.cycle(), 2);
+        history.rewind(&mut code, 1);
+        assert_eq!((code.pos(), code.status()), (2, &Status::Running));
+        code.poke(3, 4);
+        assert_eq!(history.step(&mut code), Ok(Step::Output(99)));
+
+        let mut code = Intcode::from_data(vec![3, -1]);
+        code.push_input(7);
+        let mut history = History::new();
+        assert!(history.step(&mut code).is_err());
+        history.step_back(&mut code);
+        assert_eq!(code.status(), &Status::Running);
+        assert_eq!(code.pending_input().len(), 1);
+    }
+}
@@ -1,7 +1,34 @@
-use std::io;
+// Lets generated code refer to `intcode::` from inside this crate's own tests
+extern crate self as intcode;
+
+pub mod analysis;
+pub mod ascii;
+pub mod asm;
+pub mod batch;
+pub mod compile;
+pub mod debugger;
+pub mod devices;
+pub mod disasm;
+mod error;
+pub mod history;
+pub mod limits;
+mod memory;
+pub mod network;
+mod observer;
+pub mod pipeline;
+pub mod profile;
+pub mod snapshot;
+pub mod symbolic;
+pub mod trace;
+
+pub use error::IntcodeError;
+pub use memory::Memory;
+pub use observer::Observer;
+
+use std::collections::VecDeque;
 use std::path::Path;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Op {
     Stop,
     Add,
@@ -14,6 +41,24 @@ enum Op {
     Equals,
     AdjustRelBase,
 }
+impl Op {
+    fn arity(self) -> usize {
+        match self {
+            Op::Stop => 0,
+            Op::Read | Op::Write | Op::AdjustRelBase => 1,
+            Op::JumpIfTrue | Op::JumpIfFalse => 2,
+            Op::Add | Op::Mul | Op::LessThan | Op::Equals => 3,
+        }
+    }
+    // Index of the argument the result is stored through
+    fn write_arg(self) -> Option<usize> {
+        match self {
+            Op::Add | Op::Mul | Op::LessThan | Op::Equals => Some(2),
+            Op::Read => Some(0),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 enum ParameterMode {
@@ -28,274 +73,360 @@ struct Arg {
     mode: ParameterMode,
 }
 
-#[derive(Debug)]
+// Unused trailing args are left as position mode zeros
+#[derive(Debug, Clone, Copy)]
 struct Operation {
     opcode: Op,
-    args: Vec<Arg>,
+    args: [Arg; 3],
+}
+
+/// What happened when a single instruction was executed by [`Intcode::step`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Continue,
+    Output(i64),
+    NeedInput,
+    Halted,
+}
+
+/// Where a machine stands after it last executed (or refused to execute) an instruction.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Status {
+    #[default]
+    Running,
+    AwaitingInput,
+    Halted,
+    /// Stopped by `limits::run_limited` before it was done, can be resumed.
+    BudgetExhausted,
+    Faulted(IntcodeError),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Intcode {
     pos: usize,
     rel_base: usize,
-    pub data: Vec<i64>,
-    input: Vec<i64>,
+    pub data: Memory,
+    input: VecDeque<i64>,
     pub output: Vec<i64>,
-    pub halted: bool
+    status: Status,
 }
 impl Intcode {
     pub fn from_data(data: Vec<i64>) -> Intcode {
         Intcode {
             pos: 0,
             rel_base: 0,
-            data: data,
-            input: Vec::new(),
+            data: Memory::new(data),
+            input: VecDeque::new(),
             output: Vec::new(),
-            halted: false,
+            status: Status::Running,
         }
     }
-    fn new(other: Intcode) -> Intcode {
+    /// A copy of the machine that shares memory with this one until either of them
+    /// writes to it. Much cheaper than `clone` when forking many times, as in a search.
+    pub fn fork(&self) -> Intcode {
         Intcode {
-            pos: other.pos,
-            data: other.data,
-            input: other.input,
-            output: other.output,
-            halted: other.halted,
-            rel_base: other.rel_base,
+            data: self.data.fork(),
+            input: self.input.clone(),
+            output: self.output.clone(),
+            status: self.status.clone(),
+            ..*self
         }
     }
     fn deref(&self, at: usize) -> i64 {
-        if at >= self.data.len() {
-            println!("Trying to deref outside memory");
-            0
-        } else {
-            //println!("deref@{}={}", at, self.data[at]);
-            self.data[at]
+        self.data.get(at)
+    }
+    fn param_mode(pos: usize, op: i64, shift: i64) -> Result<ParameterMode, IntcodeError> {
+        match (op / shift) % 10 {
+            0 => Ok(ParameterMode::Position),
+            1 => Ok(ParameterMode::Immediate),
+            2 => Ok(ParameterMode::Relative),
+            mode => Err(IntcodeError::BadMode { pos, mode }),
         }
     }
-    fn arg(&self, offset: usize) -> i64 {
-        self.data[self.pos + offset]
+    fn map_opcode(pos: usize, code: i64) -> Result<Op, IntcodeError> {
+        match code % 100 {
+            99 => Ok(Op::Stop),
+            1 => Ok(Op::Add),
+            2 => Ok(Op::Mul),
+            3 => Ok(Op::Read),
+            4 => Ok(Op::Write),
+            5 => Ok(Op::JumpIfTrue),
+            6 => Ok(Op::JumpIfFalse),
+            7 => Ok(Op::LessThan),
+            8 => Ok(Op::Equals),
+            9 => Ok(Op::AdjustRelBase),
+            _ => Err(IntcodeError::BadOpcode { pos, code }),
+        }
     }
-    fn param_mode(op: i64, shift: i64) -> ParameterMode {
-        match (op / shift) % 10 {
-            0 => ParameterMode::Position,
-            1 => ParameterMode::Immediate,
-            2 => ParameterMode::Relative,
-            _ => panic!("UNKNOWN PARAMETER MODE {}", op / shift % 10),
+    fn decode(&self, pos: usize) -> Result<Operation, IntcodeError> {
+        Self::decode_with(pos, |i| self.deref(pos + i))
+    }
+    // Decodes the instruction whose `i`th word is `word(i)`, at `pos` for errors
+    fn decode_with<F: Fn(usize) -> i64>(pos: usize, word: F) -> Result<Operation, IntcodeError> {
+        let code = word(0);
+        let opcode = Self::map_opcode(pos, code)?;
+        let mut args = [Arg {
+            value: 0,
+            mode: ParameterMode::Position,
+        }; 3];
+        let mut shift = 100;
+        for (i, arg) in args.iter_mut().enumerate().take(opcode.arity()) {
+            *arg = Arg {
+                value: word(i + 1),
+                mode: Self::param_mode(pos, code, shift)?,
+            };
+            shift *= 10;
         }
+        Ok(Operation { opcode, args })
     }
-    fn param_modes(&self) -> [ParameterMode; 3] {
-        let mode1 = Self::param_mode(self.arg(0), 100);
-        let mode2 = Self::param_mode(self.arg(0), 1000);
-        let mode3 = Self::param_mode(self.arg(0), 10000);
-        [mode1, mode2, mode3]
-    }
-    fn map_opcode(code: i64) -> Op {
-        match code {
-            99 => Op::Stop,
-            1 => Op::Add,
-            2 => Op::Mul,
-            3 => Op::Read,
-            4 => Op::Write,
-            5 => Op::JumpIfTrue,
-            6 => Op::JumpIfFalse,
-            7 => Op::LessThan,
-            8 => Op::Equals,
-            9 => Op::AdjustRelBase,
-            _ => {
-                panic!("UNKNOWN OP CODE {}", code);
-            }
+    fn op(&self) -> Result<Operation, IntcodeError> {
+        self.decode(self.pos)
+    }
+
+    fn address(&self, addr: i64) -> Result<usize, IntcodeError> {
+        if addr < 0 {
+            Err(IntcodeError::NegativeAddress { pos: self.pos, addr })
+        } else {
+            Ok(addr as usize)
         }
     }
-    fn op(&self) -> Operation {
-        let code = self.arg(0);
-        let opcode = code % 100;
-        let op = Self::map_opcode(opcode);
-        let args: Vec<Arg> = match op {
-            Op::Add | Op::Mul | Op::LessThan | Op::Equals => {
-                let modes = self.param_modes();
-                let values = [self.arg(1), self.arg(2), self.arg(3)];
-                modes
-                    .iter()
-                    .zip(values.iter())
-                    .map(|(mode, val)| Arg {
-                        value: *val,
-                        mode: *mode,
-                    })
-                    .collect()
-            }
-            Op::Read | Op::Write | Op::AdjustRelBase => vec![Arg {
-                value: self.arg(1),
-                mode: Self::param_mode(code, 100),
-            }],
-            Op::JumpIfFalse | Op::JumpIfTrue => vec![Arg {
-                value: self.arg(1),
-                mode: Self::param_mode(code, 100)
-            }, Arg {
-                value: self.arg(2),
-                mode: Self::param_mode(code, 1000)
-            }],
-            Op::Stop => vec![],
-        };
-        Operation {
-            opcode: op,
-            args: args,
+
+    // `rel_base` is never negative, so it always fits back into an i64
+    fn relative(&self, offset: i64) -> Result<usize, IntcodeError> {
+        match offset.checked_add(self.rel_base as i64) {
+            Some(addr) => self.address(addr),
+            None => Err(IntcodeError::AddressOverflow { pos: self.pos }),
         }
     }
 
-    fn write_at(&mut self, arg: Arg, val: i64) {
+    fn write_at<O: Observer + ?Sized>(&mut self, arg: Arg, val: i64, observer: &mut O) -> Result<(), IntcodeError> {
+        let addr = match arg.mode {
+            ParameterMode::Immediate => return Err(IntcodeError::IllegalWrite { pos: self.pos }),
+            ParameterMode::Position => self.address(arg.value)?,
+            ParameterMode::Relative => self.relative(arg.value)?,
+        };
+        self.poke(addr, val);
+        observer.write(addr, val);
+        Ok(())
+    }
+
+    fn load<O: Observer + ?Sized>(&self, arg: Arg, observer: &mut O) -> Result<i64, IntcodeError> {
         let addr = match arg.mode {
-            ParameterMode::Immediate => panic!("Cannot write in immediate mode"),
-            ParameterMode::Position => arg.value as usize,
-            ParameterMode::Relative => (arg.value + self.rel_base as i64) as usize,
+            ParameterMode::Immediate => return Ok(arg.value),
+            ParameterMode::Position => self.address(arg.value)?,
+            ParameterMode::Relative => self.relative(arg.value)?,
         };
-        if addr >= self.data.len() {
-            self.data.extend(vec![0; addr - self.data.len() + 1]);
+        let val = self.deref(addr);
+        observer.read(addr, val);
+        Ok(val)
+    }
+
+    pub fn push_input(&mut self, value: i64) {
+        self.input.push_back(value);
+    }
+
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub fn rel_base(&self) -> usize {
+        self.rel_base
+    }
+
+    pub fn pending_input(&self) -> &VecDeque<i64> {
+        &self.input
+    }
+
+    /// Reads memory the way the program would, so addresses past the end are zero.
+    pub fn peek(&self, addr: usize) -> i64 {
+        self.deref(addr)
+    }
+
+    pub fn poke(&mut self, addr: usize, val: i64) {
+        self.data.set(addr, val);
+    }
+
+    /// Executes exactly one instruction.
+    /// Output values are handed back in the result instead of being collected in `output`.
+    /// A failing instruction leaves the machine untouched apart from its status.
+    pub fn step(&mut self) -> Result<Step, IntcodeError> {
+        self.step_with(&mut ())
+    }
+
+    /// Like `step`, reporting what the instruction does to `observer`.
+    pub fn step_with<O: Observer + ?Sized>(&mut self, observer: &mut O) -> Result<Step, IntcodeError> {
+        if let Status::Faulted(err) = &self.status {
+            return Err(err.clone());
+        }
+        match self.exec(observer) {
+            Ok(step) => {
+                self.status = match step {
+                    Step::Continue | Step::Output(_) => Status::Running,
+                    Step::NeedInput => Status::AwaitingInput,
+                    Step::Halted => Status::Halted,
+                };
+                Ok(step)
+            }
+            Err(err) => {
+                self.status = Status::Faulted(err.clone());
+                Err(err)
+            }
         }
-        //println!("write@{}:={}", addr, val);
-        self.data[addr] = val;
     }
 
-    fn arg_to_val(&self, arg: Arg) -> i64 {
-        //println!("Getting arg {:?} from base {}", arg, self.rel_base);
-        match arg.mode {
-            ParameterMode::Immediate => arg.value,
-            ParameterMode::Position => self.deref(arg.value as usize),
-            ParameterMode::Relative => self.deref((arg.value + self.rel_base as i64) as usize),
+    fn exec<O: Observer + ?Sized>(&mut self, observer: &mut O) -> Result<Step, IntcodeError> {
+        let op = self.op()?;
+        let args = &op.args;
+        let next = self.pos + op.opcode.arity() + 1;
+        // Leave the instruction pointer on a read until input arrives
+        if op.opcode == Op::Read && self.input.is_empty() {
+            return Ok(Step::NeedInput);
+        }
+        let words = [self.deref(self.pos), args[0].value, args[1].value, args[2].value];
+        observer.fetch(self.pos, &words[..=op.opcode.arity()]);
+        match op.opcode {
+            Op::Stop => {
+                observer.halt(self.pos);
+                return Ok(Step::Halted);
+            }
+            Op::Add => self.do_op(args, add, observer)?,
+            Op::Mul => self.do_op(args, mul, observer)?,
+            Op::LessThan => self.do_op(args, less_than, observer)?,
+            Op::Equals => self.do_op(args, equals, observer)?,
+            Op::Read => {
+                let val = self.input[0];
+                self.write_at(args[0], val, observer)?;
+                self.input.pop_front();
+                observer.input(val);
+            }
+            Op::Write => {
+                let val = self.load(args[0], observer)?;
+                observer.output(val);
+                self.pos = next;
+                return Ok(Step::Output(val));
+            }
+            Op::JumpIfTrue | Op::JumpIfFalse => {
+                let test = self.load(args[0], observer)?;
+                if (test != 0) == (op.opcode == Op::JumpIfTrue) {
+                    let target = self.address(self.load(args[1], observer)?)?;
+                    observer.jump(self.pos, target);
+                    self.pos = target;
+                    return Ok(Step::Continue);
+                }
+            }
+            Op::AdjustRelBase => {
+                let val = self.load(args[0], observer)?;
+                self.rel_base = match val.checked_add(self.rel_base as i64) {
+                    Some(base) if base < 0 => {
+                        return Err(IntcodeError::NegativeRelBase { pos: self.pos, base })
+                    }
+                    Some(base) => base as usize,
+                    None => return Err(IntcodeError::AddressOverflow { pos: self.pos }),
+                };
+                observer.adjust(self.rel_base);
+            }
+        };
+        self.pos = next;
+        Ok(Step::Continue)
+    }
+
+    fn do_op<O: Observer + ?Sized>(
+        &mut self,
+        args: &[Arg; 3],
+        op: fn(i64, i64) -> Option<i64>,
+        observer: &mut O,
+    ) -> Result<(), IntcodeError> {
+        let val1 = self.load(args[0], observer)?;
+        let val2 = self.load(args[1], observer)?;
+        match op(val1, val2) {
+            Some(val) => self.write_at(args[2], val, observer),
+            None => Err(IntcodeError::ValueOverflow { pos: self.pos }),
         }
     }
 }
 
-fn add(val1: i64, val2: i64) -> i64 {
-    val1 + val2
+fn add(val1: i64, val2: i64) -> Option<i64> {
+    val1.checked_add(val2)
 }
 
-fn mul(val1: i64, val2: i64) -> i64 {
-    val1 * val2
+fn mul(val1: i64, val2: i64) -> Option<i64> {
+    val1.checked_mul(val2)
 }
 
-fn less_than(val1: i64, val2: i64) -> i64 {
+fn less_than(val1: i64, val2: i64) -> Option<i64> {
     if val1 < val2 {
-        1
+        Some(1)
     } else {
-        0
+        Some(0)
     }
 }
 
-fn equals(val1: i64, val2: i64) -> i64 {
+fn equals(val1: i64, val2: i64) -> Option<i64> {
     if val1 == val2 {
-        1
+        Some(1)
     } else {
-        0
+        Some(0)
     }
 }
 
-fn do_op(code: Intcode, op: fn(i64, i64) -> i64) -> Intcode {
-    let args = code.op().args;
-    let val1 = code.arg_to_val(args[0]);
-    let val2 = code.arg_to_val(args[1]);
-    let r = op(val1, val2);
-    let mut output = Intcode::new(code);
-    output.write_at(args[2], r);
-    output.pos += 4;
-    output
-}
-
-fn read(code: Intcode) -> Intcode {
-    let args = code.op().args;
-    let r = code.input[0];
-    let mut new_code = Intcode::new(code);
-    new_code.write_at(args[0], r);
-    new_code.pos += 2;
-    new_code.input = new_code.input[1..].to_vec();
-    new_code
-}
-
-fn write(code: Intcode) -> Intcode {
-    let args = code.op().args;
-    let val = code.arg_to_val(args[0]);
-    let mut new_code = Intcode::new(code);
-    new_code.output.push(val);
-    new_code.pos += 2;
-    new_code
-}
-
-fn jump_if_con(code: Intcode, con: fn(i64) -> bool) -> Intcode {
-    let args = code.op().args;
-    let val = code.arg_to_val(args[0]);
-    let new_pos: usize = if con(val) {
-        code.arg_to_val(args[1]) as usize
-    } else {
-        code.pos + 3
-    };
-    let mut new_code = Intcode::new(code);
-    new_code.pos = new_pos;
-    new_code
-}
-
-fn adjust_rel_base(code: Intcode) -> Intcode {
-    let args = code.op().args;
-    let val = code.arg_to_val(args[0]);
-    let new_base = code.rel_base as i64 + val;
-    let mut new_code = Intcode::new(code);
-    new_code.rel_base = new_base as usize;
-    new_code.pos += 2;
-    new_code
+/// Runs until the machine halts or waits for input; output is collected in `output`.
+pub fn run(code: &mut Intcode) -> Result<Status, IntcodeError> {
+    run_observed(code, &mut ())
 }
 
-pub fn run(input: &Intcode) -> Intcode {
-    let mut code = input.clone();
+/// Like `run`, reporting everything the machine does to `observer`.
+pub fn run_observed<O: Observer + ?Sized>(code: &mut Intcode, observer: &mut O) -> Result<Status, IntcodeError> {
     loop {
-        //println!("Running {:?}", code.op());
-        code = match code.op().opcode {
-            Op::Stop => {
-                println!("HALT");
-                code.halted = true;
-                break;
-            },
-            Op::Add => do_op(code, add),
-            Op::Mul => do_op(code, mul),
-            Op::LessThan => do_op(code, less_than),
-            Op::Equals => do_op(code, equals),
-            Op::Read => {
-                // If no input is available, halt
-                if code.input.len() > 0 {
-                    read(code)
-                } else {
-                    break;
-                }
-            },
-            Op::Write => write(code),
-            Op::JumpIfTrue => jump_if_con(code, |x| {x != 0}),
-            Op::JumpIfFalse => jump_if_con(code, |x| {x == 0}),
-            Op::AdjustRelBase => adjust_rel_base(code),
-        };
+        match code.step_with(observer)? {
+            Step::Continue => {}
+            Step::Output(val) => code.output.push(val),
+            Step::Halted | Step::NeedInput => break,
+        }
     }
-    code
+    Ok(code.status.clone())
 }
 
-pub fn intcode_from_file<P: AsRef<Path>>(file: P) -> io::Result<Intcode> {
+pub fn intcode_from_file<P: AsRef<Path>>(file: P) -> Result<Intcode, IntcodeError> {
     let data = std::fs::read_to_string(file)?;
-    let ints: Vec<i64> = data
-        .trim_end()
-        .split(",")
-        .map(|x| x.to_string().parse().unwrap())
-        .collect();
+    intcode_from_str(&data)
+}
+
+/// Parses a comma separated program, reporting the byte offset of the first bad integer.
+pub fn intcode_from_str(data: &str) -> Result<Intcode, IntcodeError> {
+    let mut ints: Vec<i64> = Vec::new();
+    let mut offset = 0;
+    for word in data.trim_end().split(',') {
+        let text = word.trim();
+        match text.parse() {
+            Ok(int) => ints.push(int),
+            Err(_) => {
+                return Err(IntcodeError::Parse {
+                    offset: offset + word.len() - word.trim_start().len(),
+                    text: text.to_string(),
+                })
+            }
+        }
+        offset += word.len() + 1;
+    }
     Ok(Intcode::from_data(ints))
 }
 
-pub fn run_with_io(code: &Intcode, input: Vec<i64>) -> Intcode {
-    let mut copy = code.clone();
-    copy.input = input;
-    run(&copy)
+/// Queues `input` behind any input still pending and runs until the machine stops.
+pub fn run_with_io(code: &mut Intcode, input: Vec<i64>) -> Result<Status, IntcodeError> {
+    code.input.extend(input);
+    run(code)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Intcode, run};
-    fn test_output(code: Intcode, expected_out: Vec<i64>) {
-        let out = run(&code);
-        assert_eq!(out.output, expected_out);
+    use super::{Intcode, IntcodeError, Status, Step, intcode_from_str, run, run_with_io};
+    fn test_output(mut code: Intcode, expected_out: Vec<i64>) {
+        assert_eq!(run(&mut code), Ok(Status::Halted));
+        assert_eq!(code.output, expected_out);
     }
     #[test]
     fn it_works() {
@@ -307,4 +438,66 @@ mod tests {
         let code = Intcode::from_data(vec![104,1125899906842624,99]);
         test_output(code, vec![1125899906842624]);
     }
+    #[test]
+    fn far_writes() {
+        // Stores 42 at 10^12 and reads it back through a relative address
+        let mut code = Intcode::from_data(vec![1101, 40, 2, 1000000000000, 109, 999999999999, 204, 1, 99]);
+        assert_eq!(run(&mut code), Ok(Status::Halted));
+        assert_eq!(code.output, vec![42]);
+        assert_eq!(code.data.len(), 9);
+    }
+    #[test]
+    fn step_reports_io() {
+        // Echoes one input value, doubled
+        let mut code = Intcode::from_data(vec![3,9,1002,9,2,9,4,9,99,0]);
+        assert_eq!(code.step(), Ok(Step::NeedInput));
+        code.push_input(21);
+        assert_eq!(code.step(), Ok(Step::Continue));
+        assert_eq!(code.step(), Ok(Step::Continue));
+        assert_eq!(code.step(), Ok(Step::Output(42)));
+        assert_eq!(code.step(), Ok(Step::Halted));
+        assert_eq!(code.step(), Ok(Step::Halted));
+        assert_eq!(code.status(), &Status::Halted);
+        assert!(code.output.is_empty());
+    }
+    #[test]
+    fn run_reports_status() {
+        let mut code = Intcode::from_data(vec![3,9,1002,9,2,9,4,9,99,0]);
+        assert_eq!(run(&mut code), Ok(Status::AwaitingInput));
+        assert_eq!(run_with_io(&mut code, vec![5]), Ok(Status::Halted));
+        assert_eq!(code.output, vec![10]);
+
+        // Jumps past the end of the program, where memory reads as opcode 0
+        let mut code = Intcode::from_data(vec![1106,0,7]);
+        let err = IntcodeError::BadOpcode { pos: 7, code: 0 };
+        assert_eq!(run(&mut code), Err(err.clone()));
+        assert_eq!(code.status(), &Status::Faulted(err.clone()));
+        assert_eq!(code.step(), Err(err));
+    }
+    #[test]
+    fn errors() {
+        let run_err = |data: Vec<i64>| run(&mut Intcode::from_data(data)).unwrap_err();
+        assert_eq!(run_err(vec![1, 0, 0, 0, 42]), IntcodeError::BadOpcode { pos: 4, code: 42 });
+        assert_eq!(run_err(vec![301, 0, 0, 0]), IntcodeError::BadMode { pos: 0, mode: 3 });
+        assert_eq!(run_err(vec![11101, 0, 0, 0]), IntcodeError::IllegalWrite { pos: 0 });
+        assert_eq!(run_err(vec![4, -3]), IntcodeError::NegativeAddress { pos: 0, addr: -3 });
+        assert_eq!(run_err(vec![109, 2, 22201, -3, 0, 0]), IntcodeError::NegativeAddress { pos: 2, addr: -1 });
+        assert_eq!(run_err(vec![109, -1, 99]), IntcodeError::NegativeRelBase { pos: 0, base: -1 });
+        assert_eq!(run_err(vec![1105, 1, -7]), IntcodeError::NegativeAddress { pos: 0, addr: -7 });
+        assert_eq!(
+            run_err(vec![109, i64::MAX, 204, 1]),
+            IntcodeError::AddressOverflow { pos: 2 }
+        );
+        assert_eq!(run_err(vec![109, i64::MAX, 109, 1]), IntcodeError::AddressOverflow { pos: 2 });
+        assert_eq!(run_err(vec![1101, i64::MAX, 1, 0, 99]), IntcodeError::ValueOverflow { pos: 0 });
+        assert_eq!(run_err(vec![1102, i64::MIN, -1, 0, 99]), IntcodeError::ValueOverflow { pos: 0 });
+    }
+    #[test]
+    fn parse_errors() {
+        assert_eq!(intcode_from_str("1,2, 3,99\n").unwrap().data.dense(), [1, 2, 3, 99]);
+        assert_eq!(
+            intcode_from_str("1,2, x3,99").unwrap_err(),
+            IntcodeError::Parse { offset: 5, text: "x3".to_string() }
+        );
+    }
 }
@@ -0,0 +1,150 @@
+use super::{Intcode, IntcodeError, Memory, Status, Step};
+use std::time::{Duration, Instant};
+
+// The clock is only looked at every this many instructions
+const CLOCK_INTERVAL: u64 = 1024;
+
+/// Bounds for `run_limited`. Everything is off by default.
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    /// Instructions to execute before giving up with `Status::BudgetExhausted`.
+    pub max_steps: Option<u64>,
+    /// Wall clock time to run for before giving up with `Status::BudgetExhausted`.
+    pub timeout: Option<Duration>,
+    /// Fault with `IntcodeError::InfiniteLoop` when the machine provably repeats itself.
+    pub detect_loops: bool,
+}
+
+impl Limits {
+    pub fn steps(max_steps: u64) -> Limits {
+        Limits {
+            max_steps: Some(max_steps),
+            ..Limits::default()
+        }
+    }
+
+    pub fn timeout(timeout: Duration) -> Limits {
+        Limits {
+            timeout: Some(timeout),
+            ..Limits::default()
+        }
+    }
+}
+
+// A machine that gets back to an earlier state without doing any I/O in between
+// will go round the same way forever. States are saved after 1, 2, 4, ... steps
+// since the last I/O and compared against every following step, so a loop is
+// found within a few times its length without keeping every state around.
+#[derive(Default)]
+struct LoopDetector {
+    since_io: u64,
+    saved: Option<(usize, usize, Memory)>,
+}
+
+impl LoopDetector {
+    fn reset(&mut self) {
+        self.since_io = 0;
+        self.saved = None;
+    }
+
+    fn repeats(&mut self, code: &Intcode) -> bool {
+        self.since_io += 1;
+        if let Some((pos, rel_base, data)) = &self.saved {
+            if *pos == code.pos && *rel_base == code.rel_base && *data == code.data {
+                return true;
+            }
+        }
+        if self.since_io.is_power_of_two() {
+            self.saved = Some((code.pos, code.rel_base, code.data.fork()));
+        }
+        false
+    }
+}
+
+/// Like `run`, but stops within `limits`. Running out of steps or time leaves the
+/// machine with `Status::BudgetExhausted`, and running it again carries on from there.
+pub fn run_limited(code: &mut Intcode, limits: &Limits) -> Result<Status, IntcodeError> {
+    let start = Instant::now();
+    let mut steps = 0;
+    let mut detector = LoopDetector::default();
+    loop {
+        let out_of_time = steps % CLOCK_INTERVAL == 0 && limits.timeout.is_some_and(|t| start.elapsed() >= t);
+        if limits.max_steps.is_some_and(|max| steps >= max) || out_of_time {
+            code.status = Status::BudgetExhausted;
+            break;
+        }
+        let input_left = code.input.len();
+        match code.step()? {
+            Step::Continue => {}
+            Step::Output(val) => {
+                code.output.push(val);
+                detector.reset();
+            }
+            Step::Halted | Step::NeedInput => break,
+        }
+        steps += 1;
+        if limits.detect_loops {
+            if code.input.len() != input_left {
+                detector.reset();
+            } else if detector.repeats(code) {
+                let err = IntcodeError::InfiniteLoop { pos: code.pos };
+                code.status = Status::Faulted(err.clone());
+                return Err(err);
+            }
+        }
+    }
+    Ok(code.status.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_limited, Limits};
+    use crate::asm::assemble;
+    use crate::{run, Intcode, IntcodeError, Status};
+    use std::time::Duration;
+
+    // Counts down from its input, printing every value, then spins forever
+    fn countdown() -> Intcode {
+        Intcode::from_data(
+            assemble(
+                "
+                        IN [n]
+                loop:   OUT [n]
+                        ADD [n], #-1, [n]
+                        JT [n], #loop
+                spin:   JT #1, #spin
+                n:      DATA 0
+                ",
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn budgets() {
+        let mut code = countdown();
+        code.push_input(3);
+        // IN, then OUT, ADD, JT per value
+        assert_eq!(run_limited(&mut code, &Limits::steps(5)), Ok(Status::BudgetExhausted));
+        assert_eq!(code.output, vec![3, 2]);
+        assert_eq!(code.status(), &Status::BudgetExhausted);
+        assert_eq!(run_limited(&mut code, &Limits::steps(5)), Ok(Status::BudgetExhausted));
+        assert_eq!(code.output, vec![3, 2, 1]);
+
+        let timeout = Limits::timeout(Duration::from_millis(10));
+        assert_eq!(run_limited(&mut code, &timeout), Ok(Status::BudgetExhausted));
+        assert_eq!(code.pos(), 11);
+
+        let mut code = countdown();
+        let limits = Limits {
+            detect_loops: true,
+            ..Limits::steps(1_000_000)
+        };
+        assert_eq!(run_limited(&mut code, &limits), Ok(Status::AwaitingInput));
+        code.push_input(40);
+        let err = IntcodeError::InfiniteLoop { pos: 11 };
+        assert_eq!(run_limited(&mut code, &limits), Err(err.clone()));
+        assert_eq!(code.output.len(), 40);
+        assert_eq!(run(&mut code), Err(err));
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::ops::{Index, IndexMut};
+use std::sync::Arc;
+
+const PAGE_SIZE: usize = 256;
+// Writes this close to the end of the dense region grow it instead of starting a far page
+const DENSE_SLACK: usize = 1 << 16;
+
+type Page = [i64; PAGE_SIZE];
+
+/// Intcode memory: the program image and anything written near it is kept in a
+/// run of pages from address zero, far away addresses live in pages that are
+/// created on first write. Every address reads as zero until it's written.
+///
+/// Pages are shared between forks and only copied when one of them writes to a
+/// shared page, so forking is cheap however large memory gets. `Clone` copies
+/// everything up front.
+#[derive(Debug, Default)]
+pub struct Memory {
+    // Pages for addresses 0.., with everything below `len` in the dense region
+    pages: Vec<Arc<Page>>,
+    len: usize,
+    // Numbered past the end of `pages`
+    far: HashMap<usize, Arc<Page>>,
+}
+
+fn page_of(values: &[i64]) -> Arc<Page> {
+    let mut page = [0; PAGE_SIZE];
+    page[..values.len()].copy_from_slice(values);
+    Arc::new(page)
+}
+
+impl Memory {
+    pub fn new(image: Vec<i64>) -> Memory {
+        Memory {
+            pages: image.chunks(PAGE_SIZE).map(page_of).collect(),
+            len: image.len(),
+            far: HashMap::new(),
+        }
+    }
+
+    /// A copy that shares every page with this one until either side writes to it.
+    pub fn fork(&self) -> Memory {
+        Memory {
+            pages: self.pages.clone(),
+            len: self.len,
+            far: self.far.clone(),
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, addr: usize) -> i64 {
+        match self.pages.get(addr / PAGE_SIZE) {
+            Some(page) => page[addr % PAGE_SIZE],
+            None => self.get_far(addr),
+        }
+    }
+
+    #[cold]
+    fn get_far(&self, addr: usize) -> i64 {
+        self.far.get(&(addr / PAGE_SIZE)).map_or(0, |page| page[addr % PAGE_SIZE])
+    }
+
+    #[inline]
+    pub fn set(&mut self, addr: usize, val: i64) {
+        *self.cell(addr) = val;
+    }
+
+    #[inline]
+    fn cell(&mut self, addr: usize) -> &mut i64 {
+        if addr / PAGE_SIZE < self.pages.len() {
+            self.len = self.len.max(addr + 1);
+            &mut Arc::make_mut(&mut self.pages[addr / PAGE_SIZE])[addr % PAGE_SIZE]
+        } else {
+            self.cell_far(addr)
+        }
+    }
+
+    #[cold]
+    fn cell_far(&mut self, addr: usize) -> &mut i64 {
+        if addr - self.len < DENSE_SLACK {
+            self.grow(addr + 1);
+            return self.cell(addr);
+        }
+        let page = self
+            .far
+            .entry(addr / PAGE_SIZE)
+            .or_insert_with(|| Arc::new([0; PAGE_SIZE]));
+        &mut Arc::make_mut(page)[addr % PAGE_SIZE]
+    }
+
+    // Far pages that end up inside the dense region are moved into it as they are
+    fn grow(&mut self, len: usize) {
+        for n in self.pages.len()..=(len - 1) / PAGE_SIZE {
+            let page = match self.far.remove(&n) {
+                Some(page) => {
+                    if let Some(last) = page.iter().rposition(|v| *v != 0) {
+                        self.len = self.len.max(n * PAGE_SIZE + last + 1);
+                    }
+                    page
+                }
+                None => Arc::new([0; PAGE_SIZE]),
+            };
+            self.pages.push(page);
+        }
+        self.len = self.len.max(len);
+    }
+
+    /// Length of the dense region, which starts out as the program image.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0 && self.far.is_empty()
+    }
+
+    /// A copy of the dense region.
+    pub fn dense(&self) -> Vec<i64> {
+        let mut dense: Vec<i64> = self.pages.iter().flat_map(|page| page.iter().cloned()).collect();
+        dense.truncate(self.len);
+        dense
+    }
+
+    /// Pages past the dense region as `(start, values)`, in address order.
+    pub fn far_pages(&self) -> Vec<(usize, &[i64])> {
+        let mut pages: Vec<(&usize, &Arc<Page>)> = self.far.iter().collect();
+        pages.sort_by_key(|(n, _)| **n);
+        pages.into_iter().map(|(n, page)| (n * PAGE_SIZE, &page[..])).collect()
+    }
+
+    fn page(&self, n: usize) -> Option<&Arc<Page>> {
+        self.pages.get(n).or_else(|| self.far.get(&n))
+    }
+
+    // Whether page `n` holds the same values as `page`, missing pages being all zeros
+    fn same_page(&self, n: usize, page: &Arc<Page>) -> bool {
+        match self.page(n) {
+            Some(mine) => Arc::ptr_eq(mine, page) || mine[..] == page[..],
+            None => page.iter().all(|v| *v == 0),
+        }
+    }
+}
+
+impl Clone for Memory {
+    fn clone(&self) -> Memory {
+        let copy = |page: &Arc<Page>| Arc::new(**page);
+        Memory {
+            pages: self.pages.iter().map(copy).collect(),
+            len: self.len,
+            far: self.far.iter().map(|(n, page)| (*n, copy(page))).collect(),
+        }
+    }
+}
+
+impl PartialEq for Memory {
+    fn eq(&self, other: &Memory) -> bool {
+        let same = |a: &Memory, b: &Memory| {
+            a.pages.iter().enumerate().all(|(n, page)| b.same_page(n, page))
+                && a.far.iter().all(|(n, page)| b.same_page(*n, page))
+        };
+        same(self, other) && same(other, self)
+    }
+}
+
+impl Eq for Memory {}
+
+impl Index<usize> for Memory {
+    type Output = i64;
+
+    fn index(&self, addr: usize) -> &i64 {
+        match self.page(addr / PAGE_SIZE) {
+            Some(page) => &page[addr % PAGE_SIZE],
+            None => &0,
+        }
+    }
+}
+
+impl IndexMut<usize> for Memory {
+    fn index_mut(&mut self, addr: usize) -> &mut i64 {
+        self.cell(addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Memory, PAGE_SIZE};
+    use std::sync::Arc;
+
+    #[test]
+    fn far_addresses_are_paged() {
+        let mut mem = Memory::new(vec![1, 2, 3]);
+        mem.set(1_000_000_000_000, 42);
+        mem.set(10, 7);
+        assert_eq!(mem.len(), 11);
+        assert_eq!(mem.get(1_000_000_000_000), 42);
+        assert_eq!(mem[999_999_999_999], 0);
+        assert_eq!(mem[10], 7);
+        assert_eq!(mem.get(usize::MAX), 0);
+        assert_eq!(mem.far_pages().len(), 1);
+        assert_eq!(mem.dense(), [1, 2, 3, 0, 0, 0, 0, 0, 0, 0, 7]);
+    }
+
+    #[test]
+    fn growing_absorbs_pages() {
+        let mut mem = Memory::new(vec![]);
+        let far = 3 * PAGE_SIZE * 1000;
+        mem.set(far + 5, 1);
+        mem.set(far + PAGE_SIZE + 5, 2);
+        let before = mem.clone();
+        // Walk the dense region up to the first page
+        let mut addr = 0;
+        while addr < far {
+            addr += 60_000;
+            mem.set(addr.min(far), 0);
+        }
+        assert_eq!(mem.get(far + 5), 1);
+        assert_eq!(mem.get(far + PAGE_SIZE + 5), 2);
+        assert_eq!(mem.len(), far + 6);
+        assert_eq!(mem.far.len(), 1);
+        assert_eq!(mem, before);
+        mem.set(far + 5, 9);
+        assert_ne!(mem, before);
+    }
+
+    #[test]
+    fn forks_share_pages() {
+        let mut mem = Memory::new((0..3 * PAGE_SIZE as i64).collect());
+        mem.set(1_000_000_000_000, 42);
+        let mut fork = mem.fork();
+        fork.set(PAGE_SIZE + 1, -1);
+        fork.set(1_000_000_000_001, 43);
+        assert_eq!(mem.get(PAGE_SIZE + 1), PAGE_SIZE as i64 + 1);
+        assert_eq!(mem.get(1_000_000_000_001), 0);
+        assert_eq!(fork.get(PAGE_SIZE + 1), -1);
+        assert_eq!(fork.get(1_000_000_000_000), 42);
+        assert!(Arc::ptr_eq(&mem.pages[0], &fork.pages[0]));
+        assert!(!Arc::ptr_eq(&mem.pages[1], &fork.pages[1]));
+        assert!(!Arc::ptr_eq(&mem.pages[0], &mem.clone().pages[0]));
+        fork.set(PAGE_SIZE + 1, PAGE_SIZE as i64 + 1);
+        fork.set(1_000_000_000_001, 0);
+        assert_eq!(fork, mem);
+    }
+}
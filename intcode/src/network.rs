@@ -0,0 +1,181 @@
+use super::{run, Intcode, IntcodeError, Status};
+use std::collections::VecDeque;
+
+/// A message on the network. Machines send one by outputting `dest, x, y`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packet {
+    pub src: i64,
+    pub dest: i64,
+    pub x: i64,
+    pub y: i64,
+}
+
+/// What the hook passed to `Network::run` gets to see.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// A packet for an address outside of the network, like a monitor at 255.
+    Packet(Packet),
+    /// Every queue is empty and every machine asked for input without getting any.
+    Idle,
+}
+
+/// What the network should do after an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    Send(Packet),
+    Stop,
+}
+
+/// Runs a set of machines that address each other by their index.
+pub struct Network {
+    machines: Vec<Intcode>,
+}
+
+impl Network {
+    /// Boots `size` copies of `code`, each receiving its address as its first input.
+    pub fn new(code: &Intcode, size: usize) -> Network {
+        let machines = (0..size)
+            .map(|addr| {
+                let mut machine = code.clone();
+                machine.push_input(addr as i64);
+                machine
+            })
+            .collect();
+        Network { machines }
+    }
+
+    pub fn machines(&self) -> &[Intcode] {
+        &self.machines
+    }
+
+    /// Queues a packet for its destination. Returns it again if nobody has that address.
+    pub fn send(&mut self, packet: Packet) -> Option<Packet> {
+        let machine = if packet.dest >= 0 {
+            self.machines.get_mut(packet.dest as usize)
+        } else {
+            None
+        };
+        match machine {
+            Some(machine) => {
+                machine.push_input(packet.x);
+                machine.push_input(packet.y);
+                None
+            }
+            None => Some(packet),
+        }
+    }
+
+    /// Lets every machine run in turn until `hook` says `Stop` or all of them have halted.
+    /// A machine without pending input reads -1. The hook is told about idleness once per
+    /// idle round, so it has to send something or stop to avoid spinning forever.
+    pub fn run<F: FnMut(Event) -> Control>(&mut self, mut hook: F) -> Result<(), IntcodeError> {
+        loop {
+            let mut idle = true;
+            let mut sent = Vec::new();
+            for (addr, machine) in self.machines.iter_mut().enumerate() {
+                if *machine.status() == Status::Halted {
+                    continue;
+                }
+                if machine.pending_input().is_empty() {
+                    machine.push_input(-1);
+                } else {
+                    idle = false;
+                }
+                run(machine)?;
+                // A half written packet stays in `output` until the rest of it arrives
+                let complete = machine.output.len() - machine.output.len() % 3;
+                for triple in machine.output.drain(..complete).collect::<Vec<i64>>().chunks(3) {
+                    sent.push(Packet {
+                        src: addr as i64,
+                        dest: triple[0],
+                        x: triple[1],
+                        y: triple[2],
+                    });
+                }
+            }
+            if self.machines.iter().all(|m| *m.status() == Status::Halted) {
+                return Ok(());
+            }
+
+            let mut events = VecDeque::new();
+            idle &= sent.is_empty();
+            for packet in sent {
+                if let Some(packet) = self.send(packet) {
+                    events.push_back(Event::Packet(packet));
+                }
+            }
+            if idle {
+                events.push_back(Event::Idle);
+            }
+            while let Some(event) = events.pop_front() {
+                match hook(event) {
+                    Control::Continue => {}
+                    // Packets the hook sends to itself come straight back to it
+                    Control::Send(packet) => {
+                        if let Some(packet) = self.send(packet) {
+                            events.push_back(Event::Packet(packet));
+                        }
+                    }
+                    Control::Stop => return Ok(()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Control, Event, Network, Packet};
+    use crate::asm::assemble;
+    use crate::Intcode;
+
+    #[test]
+    fn routes_packets_until_idle() {
+        // Doubles y and passes the packet on to the next address
+        let program = assemble(
+            "
+                    IN [addr]
+                    ADD [addr], #1, [next]
+            loop:   IN [x]
+                    EQ [x], #-1, [t]
+                    JT [t], #loop
+                    IN [y]
+                    OUT [next]
+                    OUT [x]
+                    MUL [y], #2, [y]
+                    OUT [y]
+                    JT #1, #loop
+            addr:   DATA 0
+            next:   DATA 0
+            x:      DATA 0
+            y:      DATA 0
+            t:      DATA 0
+            ",
+        )
+        .unwrap();
+        let mut net = Network::new(&Intcode::from_data(program), 3);
+        assert_eq!(net.machines().len(), 3);
+        let mut events = Vec::new();
+        net.run(|event| {
+            events.push(event);
+            match events.len() {
+                1 => Control::Send(Packet { src: 255, dest: 0, x: 7, y: 1 }),
+                4 => Control::Send(Packet { src: 255, dest: -1, x: 0, y: 0 }),
+                5 => Control::Stop,
+                _ => Control::Continue,
+            }
+        })
+        .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Idle,
+                Event::Packet(Packet { src: 2, dest: 3, x: 7, y: 8 }),
+                Event::Idle,
+                Event::Idle,
+                Event::Packet(Packet { src: 255, dest: -1, x: 0, y: 0 }),
+            ]
+        );
+    }
+}
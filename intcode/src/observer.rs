@@ -0,0 +1,110 @@
+/// Callbacks for everything a machine does, passed to `Intcode::step_with` or `run_observed`.
+/// All of them do nothing by default, so an observer only implements what it needs.
+///
+/// Events are reported as they happen. An instruction that faults may already have
+/// reported some of its reads, and one that waits for input reports nothing.
+pub trait Observer {
+    /// The instruction at `addr` decoded fine and is about to run. `instruction` holds
+    /// its words, the opcode followed by its parameters.
+    fn fetch(&mut self, _addr: usize, _instruction: &[i64]) {}
+    /// An operand was loaded from memory. Immediate operands aren't reads.
+    fn read(&mut self, _addr: usize, _val: i64) {}
+    fn write(&mut self, _addr: usize, _val: i64) {}
+    /// An input value was stored and taken off the queue.
+    fn input(&mut self, _val: i64) {}
+    fn output(&mut self, _val: i64) {}
+    /// A conditional jump was taken. Jumps not taken just fall through.
+    fn jump(&mut self, _from: usize, _to: usize) {}
+    /// The relative base was moved to `rel_base`.
+    fn adjust(&mut self, _rel_base: usize) {}
+    fn halt(&mut self, _addr: usize) {}
+}
+
+/// Observes nothing; what `Intcode::step` and `run` use.
+impl Observer for () {}
+
+#[cfg(test)]
+mod tests {
+    use super::Observer;
+    use crate::asm::assemble;
+    use crate::{run_observed, Intcode, Status};
+
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    impl Observer for Log {
+        fn fetch(&mut self, addr: usize, instruction: &[i64]) {
+            self.0.push(format!("fetch {} {:?}", addr, instruction));
+        }
+        fn read(&mut self, addr: usize, val: i64) {
+            self.0.push(format!("read {} {}", addr, val));
+        }
+        fn write(&mut self, addr: usize, val: i64) {
+            self.0.push(format!("write {} {}", addr, val));
+        }
+        fn input(&mut self, val: i64) {
+            self.0.push(format!("input {}", val));
+        }
+        fn output(&mut self, val: i64) {
+            self.0.push(format!("output {}", val));
+        }
+        fn jump(&mut self, from: usize, to: usize) {
+            self.0.push(format!("jump {} {}", from, to));
+        }
+        fn adjust(&mut self, rel_base: usize) {
+            self.0.push(format!("adjust {}", rel_base));
+        }
+        fn halt(&mut self, addr: usize) {
+            self.0.push(format!("halt {}", addr));
+        }
+    }
+
+    #[test]
+    fn reports_every_event() {
+        let program = assemble(
+            "
+                    IN [x]
+                    ARB #2
+                    JT [x], #out
+                    HLT
+            out:    OUT [x]
+                    HLT
+            x:      DATA 0
+            ",
+        )
+        .unwrap();
+        let mut code = Intcode::from_data(program);
+        let mut log = Log::default();
+        assert_eq!(run_observed(&mut code, &mut log), Ok(Status::AwaitingInput));
+        assert!(log.0.is_empty());
+        code.push_input(7);
+        assert_eq!(run_observed(&mut code, &mut log), Ok(Status::Halted));
+        assert_eq!(code.output, vec![7]);
+        assert_eq!(
+            log.0,
+            vec![
+                "fetch 0 [3, 11]",
+                "write 11 7",
+                "input 7",
+                "fetch 2 [109, 2]",
+                "adjust 2",
+                "fetch 4 [1005, 11, 8]",
+                "read 11 7",
+                "jump 4 8",
+                "fetch 8 [4, 11]",
+                "read 11 7",
+                "output 7",
+                "fetch 10 [99]",
+                "halt 10",
+            ]
+        );
+
+        // Input that can't be stored stays on the queue and isn't reported
+        let mut code = Intcode::from_data(vec![203, -1, 99]);
+        code.push_input(7);
+        let mut log = Log::default();
+        assert!(run_observed(&mut code, &mut log).is_err());
+        assert_eq!(log.0, vec!["fetch 0 [203, -1]"]);
+        assert_eq!(code.pending_input().len(), 1);
+    }
+}
@@ -0,0 +1,151 @@
+use super::{run, Intcode, IntcodeError, Status};
+
+/// How a pipeline run ended. `outputs` holds everything each node wrote, by node id,
+/// including values that were passed on to other nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub deadlocked: bool,
+    pub outputs: Vec<Vec<i64>>,
+}
+
+/// Machines wired together output to input. A node may feed several others, and
+/// edges may form loops.
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    nodes: Vec<Intcode>,
+    edges: Vec<(usize, usize)>,
+    // How much of each node's output has been passed on already
+    forwarded: Vec<usize>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// Adds a machine and returns its node id.
+    pub fn add_node(&mut self, code: Intcode) -> usize {
+        self.nodes.push(code);
+        self.forwarded.push(0);
+        self.nodes.len() - 1
+    }
+
+    /// Every value `from` outputs from now on is also queued as input of `to`.
+    pub fn connect(&mut self, from: usize, to: usize) {
+        assert!(from < self.nodes.len() && to < self.nodes.len(), "no such node");
+        self.edges.push((from, to));
+    }
+
+    /// Queues input for a node, ahead of anything its inputs send it later.
+    pub fn seed(&mut self, node: usize, inputs: &[i64]) {
+        for val in inputs {
+            self.nodes[node].push_input(*val);
+        }
+    }
+
+    pub fn node(&self, node: usize) -> &Intcode {
+        &self.nodes[node]
+    }
+
+    /// Runs nodes in turn until all of them have halted or the ones left are all
+    /// waiting for input that will never come.
+    pub fn run(&mut self) -> Result<Outcome, IntcodeError> {
+        loop {
+            let mut progress = false;
+            for node in 0..self.nodes.len() {
+                let code = &mut self.nodes[node];
+                let blocked = match code.status() {
+                    Status::Halted => true,
+                    Status::AwaitingInput => code.pending_input().is_empty(),
+                    _ => false,
+                };
+                if blocked {
+                    continue;
+                }
+                run(code)?;
+                progress = true;
+                self.forward(node);
+            }
+            if !progress {
+                break;
+            }
+        }
+        Ok(Outcome {
+            deadlocked: self.nodes.iter().any(|code| *code.status() != Status::Halted),
+            outputs: self.nodes.iter().map(|code| code.output.clone()).collect(),
+        })
+    }
+
+    fn forward(&mut self, from: usize) {
+        let new = self.nodes[from].output[self.forwarded[from]..].to_vec();
+        self.forwarded[from] += new.len();
+        for &(_, to) in self.edges.iter().filter(|(f, _)| *f == from) {
+            self.nodes[to].input.extend(&new);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pipeline;
+    use crate::asm::assemble;
+    use crate::Intcode;
+
+    #[test]
+    fn fan_out_and_loops() {
+        // Adds its first input to everything else it reads, passing on a zero before halting
+        let adder = Intcode::from_data(
+            assemble(
+                "
+                        IN [n]
+                loop:   IN [x]
+                        JF [x], #end
+                        ADD [x], [n], [x]
+                        OUT [x]
+                        JT #1, #loop
+                end:    OUT [x]
+                        HLT
+                n:      DATA 0
+                x:      DATA 0
+                ",
+            )
+            .unwrap(),
+        );
+        let mut pipeline = Pipeline::new();
+        let a = pipeline.add_node(adder.clone());
+        let b = pipeline.add_node(adder.clone());
+        let c = pipeline.add_node(adder.clone());
+        pipeline.connect(a, b);
+        pipeline.connect(a, c);
+        pipeline.seed(a, &[1, 5, 0]);
+        pipeline.seed(b, &[10]);
+        pipeline.seed(c, &[-6]);
+        let outcome = pipeline.run().unwrap();
+        assert!(!outcome.deadlocked);
+        assert_eq!(outcome.outputs, vec![vec![6, 0], vec![16, 0], vec![0, 0]]);
+
+        // Two nodes passing a value back and forth, counting it down to zero
+        let mut pipeline = Pipeline::new();
+        let a = pipeline.add_node(adder.clone());
+        let b = pipeline.add_node(adder.clone());
+        pipeline.connect(a, b);
+        pipeline.connect(b, a);
+        pipeline.seed(a, &[-1, 4]);
+        pipeline.seed(b, &[-1]);
+        let outcome = pipeline.run().unwrap();
+        assert!(!outcome.deadlocked);
+        assert_eq!(outcome.outputs, vec![vec![3, 1, 0], vec![2, 0, 0]]);
+        assert_eq!(pipeline.node(a).pending_input().len(), 1);
+
+        // Nothing ever sends b its terminating zero
+        let mut pipeline = Pipeline::new();
+        let a = pipeline.add_node(adder.clone());
+        let b = pipeline.add_node(adder);
+        pipeline.connect(a, b);
+        pipeline.seed(a, &[1, 5]);
+        pipeline.seed(b, &[1]);
+        let outcome = pipeline.run().unwrap();
+        assert!(outcome.deadlocked);
+        assert_eq!(outcome.outputs, vec![vec![6], vec![7]]);
+    }
+}
@@ -0,0 +1,126 @@
+use super::disasm::{disassemble_at, mnemonic};
+use super::{run_observed, Intcode, IntcodeError, Observer, Status, Step};
+use std::collections::{BTreeMap, HashMap};
+
+/// Execution counts gathered by running a program through `Profiler::run`.
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    pub cycles: u64,
+    /// Operand loads from memory, not counting instruction fetches.
+    pub reads: u64,
+    pub writes: u64,
+    /// Highest address fetched, read or written.
+    pub max_addr: usize,
+    pub by_addr: HashMap<usize, u64>,
+    /// Keyed by mnemonic, like `ADD`.
+    pub by_op: BTreeMap<&'static str, u64>,
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler::default()
+    }
+
+    /// Like `Intcode::step`, counting the instruction once it has been fetched.
+    pub fn step(&mut self, code: &mut Intcode) -> Result<Step, IntcodeError> {
+        code.step_with(self)
+    }
+
+    /// Like `run`, profiling every instruction.
+    pub fn run(&mut self, code: &mut Intcode) -> Result<Status, IntcodeError> {
+        run_observed(code, self)
+    }
+
+    /// Totals, the `top` most executed instructions with their disassembly, and counts per opcode.
+    pub fn report(&self, code: &Intcode, top: usize) -> String {
+        let share = |count: u64| 100.0 * count as f64 / self.cycles.max(1) as f64;
+        let mut report = format!(
+            "{} cycles, {} reads, {} writes, highest address {}\n\n      count       %  instruction\n",
+            self.cycles, self.reads, self.writes, self.max_addr
+        );
+        let mut hot: Vec<(&usize, &u64)> = self.by_addr.iter().collect();
+        hot.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (addr, count) in hot.into_iter().take(top) {
+            report += &format!("{:>11} {:>6.2}%  {}\n", count, share(*count), disassemble_at(code, *addr));
+        }
+        report += "\n      count       %  opcode\n";
+        let mut ops: Vec<(&&str, &u64)> = self.by_op.iter().collect();
+        ops.sort_by(|a, b| b.1.cmp(a.1));
+        for (op, count) in ops {
+            report += &format!("{:>11} {:>6.2}%  {}\n", count, share(*count), op);
+        }
+        report
+    }
+}
+
+impl Observer for Profiler {
+    fn fetch(&mut self, addr: usize, instruction: &[i64]) {
+        // Only instructions that decoded are fetched
+        let op = match Intcode::map_opcode(addr, instruction[0]) {
+            Ok(op) => op,
+            Err(_) => return,
+        };
+        self.cycles += 1;
+        *self.by_addr.entry(addr).or_insert(0) += 1;
+        *self.by_op.entry(mnemonic(op)).or_insert(0) += 1;
+        self.max_addr = self.max_addr.max(addr + instruction.len() - 1);
+    }
+
+    fn read(&mut self, addr: usize, _val: i64) {
+        self.reads += 1;
+        self.max_addr = self.max_addr.max(addr);
+    }
+
+    fn write(&mut self, addr: usize, _val: i64) {
+        self.writes += 1;
+        self.max_addr = self.max_addr.max(addr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Profiler;
+    use crate::asm::assemble;
+    use crate::{Intcode, Status};
+
+    #[test]
+    fn counts_and_report() {
+        let program = assemble(
+            "
+                    ARB #100
+            loop:   ADD [rb+0], #1, [rb+0]
+                    LT [rb+0], #3, [t]
+                    JT [t], #loop
+                    HLT
+            t:      DATA 0
+            ",
+        )
+        .unwrap();
+        let mut code = Intcode::from_data(program);
+        let mut profiler = Profiler::new();
+        assert_eq!(profiler.run(&mut code), Ok(Status::Halted));
+        assert_eq!(profiler.cycles, 11);
+        assert_eq!(profiler.reads, 9);
+        assert_eq!(profiler.writes, 6);
+        assert_eq!(profiler.max_addr, 100);
+        assert_eq!(profiler.by_addr[&2], 3);
+        assert_eq!(profiler.by_op["JT"], 3);
+        assert_eq!(
+            profiler.report(&code, 2),
+            "\
+11 cycles, 9 reads, 6 writes, highest address 100
+
+      count       %  instruction
+          3  27.27%      2: ADD [rb], #1, [rb]
+          3  27.27%      6: LT [rb], #3, [14]
+
+      count       %  opcode
+          3  27.27%  ADD
+          3  27.27%  JT
+          3  27.27%  LT
+          1   9.09%  ARB
+          1   9.09%  HLT
+"
+        );
+    }
+}
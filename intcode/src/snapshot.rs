@@ -0,0 +1,292 @@
+use super::{Intcode, IntcodeError, Memory, Op, Status};
+use std::collections::VecDeque;
+use std::path::Path;
+
+// A snapshot is a small line based text file:
+//
+//     intcode-snapshot 1
+//     status awaiting-input
+//     pos 1021
+//     rel_base 3071
+//     input 1,0
+//     output
+//     mem 0 1,380,379,385,1008,2563,...
+//     checksum 9f1c02a7d2e6b4c1
+//
+// `mem` may appear several times, each line giving a start address and the
+// values stored from there on. The checksum covers every line before it.
+
+const HEADER: &str = "intcode-snapshot";
+const VERSION: u32 = 1;
+
+fn invalid(message: String) -> IntcodeError {
+    IntcodeError::Snapshot(message)
+}
+
+// FNV-1a, which is stable across builds unlike `DefaultHasher`
+fn checksum(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn join(values: &[i64]) -> String {
+    values.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(",")
+}
+
+fn split(field: &str, text: &str) -> Result<Vec<i64>, IntcodeError> {
+    if text.is_empty() {
+        return Ok(vec![]);
+    }
+    text.split(',')
+        .map(|v| v.parse().map_err(|_| invalid(format!("bad value {:?} in {}", v, field))))
+        .collect()
+}
+
+fn status_name(status: &Status) -> Result<&'static str, IntcodeError> {
+    match status {
+        Status::Running => Ok("running"),
+        Status::AwaitingInput => Ok("awaiting-input"),
+        Status::Halted => Ok("halted"),
+        Status::BudgetExhausted => Ok("budget-exhausted"),
+        Status::Faulted(err) => Err(invalid(format!("cannot save a faulted machine ({})", err))),
+    }
+}
+
+/// Serializes the complete machine state. Faulted machines can't be resumed and are refused.
+pub fn snapshot(code: &Intcode) -> Result<String, IntcodeError> {
+    let input: Vec<i64> = code.input.iter().cloned().collect();
+    let mut text = format!("{} {}\n", HEADER, VERSION);
+    text += &format!("status {}\n", status_name(&code.status)?);
+    text += &format!("pos {}\n", code.pos);
+    text += &format!("rel_base {}\n", code.rel_base);
+    text += &format!("input {}\n", join(&input));
+    text += &format!("output {}\n", join(&code.output));
+    text += &format!("mem 0 {}\n", join(&code.data.dense()));
+    for (start, values) in code.data.far_pages() {
+        if let Some(last) = values.iter().rposition(|v| *v != 0) {
+            // Far pages are mostly zeros, so only their written prefix is kept
+            text += &format!("mem {} {}\n", start, join(&values[..=last]));
+        }
+    }
+    let sum = checksum(&text);
+    Ok(text + &format!("checksum {:016x}\n", sum))
+}
+
+/// Parses and validates a snapshot written by `snapshot`.
+pub fn restore(text: &str) -> Result<Intcode, IntcodeError> {
+    let body_len = text
+        .rfind("checksum ")
+        .ok_or_else(|| invalid("missing checksum".to_string()))?;
+    let (body, sum) = text.split_at(body_len);
+    let expected = format!("checksum {:016x}", checksum(body));
+    if sum.trim_end() != expected {
+        return Err(invalid("checksum mismatch".to_string()));
+    }
+
+    let mut lines = body.lines();
+    match lines.next().map(|l| l.split_whitespace().collect::<Vec<&str>>()) {
+        Some(ref words) if words.len() == 2 && words[0] == HEADER => {
+            if words[1] != VERSION.to_string() {
+                return Err(invalid(format!("unsupported version {}", words[1])));
+            }
+        }
+        _ => return Err(invalid("missing header".to_string())),
+    }
+
+    let mut status = None;
+    let mut pos = None;
+    let mut rel_base = None;
+    let mut input = None;
+    let mut output = None;
+    let mut data = Memory::default();
+    let mut written = 0;
+    for line in lines {
+        let (field, value) = match line.find(' ') {
+            Some(i) => (&line[..i], &line[i + 1..]),
+            None => (line, ""),
+        };
+        let duplicate = match field {
+            "status" => status
+                .replace(match value {
+                    "running" => Status::Running,
+                    "awaiting-input" => Status::AwaitingInput,
+                    "halted" => Status::Halted,
+                    "budget-exhausted" => Status::BudgetExhausted,
+                    _ => return Err(invalid(format!("unknown status {:?}", value))),
+                })
+                .is_some(),
+            "pos" | "rel_base" => {
+                // Both have to stay usable as signed Intcode values
+                let num = value
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|num| *num >= 0)
+                    .ok_or_else(|| invalid(format!("bad {} {:?}", field, value)))?
+                    as usize;
+                if field == "pos" {
+                    pos.replace(num).is_some()
+                } else {
+                    rel_base.replace(num).is_some()
+                }
+            }
+            "input" => input.replace(split(field, value)?).is_some(),
+            "output" => output.replace(split(field, value)?).is_some(),
+            "mem" => {
+                let (start, values) = match value.find(' ') {
+                    Some(i) => (&value[..i], split(field, &value[i + 1..])?),
+                    None => (value, vec![]),
+                };
+                // Nothing past i64::MAX can be addressed by the machine
+                let start = start
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|start| *start >= 0)
+                    .ok_or_else(|| invalid(format!("bad mem start {:?}", start)))?
+                    as usize;
+                if start < written {
+                    return Err(invalid(format!("mem at {} overlaps earlier mem", start)));
+                }
+                written = start
+                    .checked_add(values.len())
+                    .filter(|end| *end <= i64::MAX as usize + 1)
+                    .ok_or_else(|| invalid(format!("mem at {} runs past the last address", start)))?;
+                if start == 0 {
+                    data = Memory::new(values);
+                } else {
+                    for (i, val) in values.into_iter().enumerate() {
+                        data.set(start + i, val);
+                    }
+                }
+                false
+            }
+            _ => return Err(invalid(format!("unknown field {:?}", field))),
+        };
+        if duplicate {
+            return Err(invalid(format!("{} given twice", field)));
+        }
+    }
+
+    let missing = |field: &str| invalid(format!("missing {}", field));
+    let mut code = Intcode::from_data(vec![]);
+    code.data = data;
+    code.status = status.ok_or_else(|| missing("status"))?;
+    code.pos = pos.ok_or_else(|| missing("pos"))?;
+    code.rel_base = rel_base.ok_or_else(|| missing("rel_base"))?;
+    code.input = input.ok_or_else(|| missing("input"))?.into_iter().collect::<VecDeque<i64>>();
+    code.output = output.ok_or_else(|| missing("output"))?;
+
+    // The status has to agree with the instruction it stopped at
+    let op = code
+        .op()
+        .map_err(|err| invalid(format!("no instruction at pos {} ({})", code.pos, err)))?;
+    let consistent = match code.status {
+        Status::Halted => op.opcode == Op::Stop,
+        Status::AwaitingInput => op.opcode == Op::Read,
+        _ => true,
+    };
+    if !consistent {
+        return Err(invalid(format!("status {:?} does not match the instruction at pos {}", code.status, code.pos)));
+    }
+    Ok(code)
+}
+
+pub fn save_snapshot<P: AsRef<Path>>(code: &Intcode, file: P) -> Result<(), IntcodeError> {
+    std::fs::write(file, snapshot(code)?)?;
+    Ok(())
+}
+
+pub fn load_snapshot<P: AsRef<Path>>(file: P) -> Result<Intcode, IntcodeError> {
+    restore(&std::fs::read_to_string(file)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{checksum, restore, snapshot};
+    use crate::asm::assemble;
+    use crate::{run, run_with_io, Intcode, IntcodeError, Status};
+
+    #[test]
+    fn resumes_from_snapshot() {
+        let program = assemble(
+            "
+            ; prints the running total of its inputs until it reads a zero
+            loop:   IN [x]
+                    JF [x], #end
+                    ADD [x], [sum], [sum]
+                    OUT [sum]
+                    JT #1, #loop
+            end:    HLT
+            x:      DATA 0
+            sum:    DATA 0
+            ",
+        )
+        .unwrap();
+        let mut code = Intcode::from_data(program);
+        run_with_io(&mut code, vec![1, 2, 3]).unwrap();
+        code.push_input(4);
+
+        let mut resumed = restore(&snapshot(&code).unwrap()).unwrap();
+        assert_eq!(resumed.status(), &Status::AwaitingInput);
+        assert_eq!(resumed.output, vec![1, 3, 6]);
+        assert_eq!(run_with_io(&mut resumed, vec![0]), Ok(Status::Halted));
+        run_with_io(&mut code, vec![0]).unwrap();
+        assert_eq!(resumed.output, vec![1, 3, 6, 10]);
+        assert_eq!(resumed.output, code.output);
+        assert_eq!(resumed.data, code.data);
+    }
+
+    #[test]
+    fn validates() {
+        let mut code = Intcode::from_data(vec![4, 3, 99, 7]);
+        run(&mut code).unwrap();
+        let text = snapshot(&code).unwrap();
+        assert!(restore(&text).is_ok());
+        let invalid = |message: &str| IntcodeError::Snapshot(message.to_string());
+        assert_eq!(restore(&text.replace("pos 2", "pos 3")).unwrap_err(), invalid("checksum mismatch"));
+
+        // Edits with a fixed up checksum
+        let body = &text[..text.rfind("checksum").unwrap()];
+        let resigned = |body: String| format!("{}checksum {:016x}\n", body, checksum(&body));
+        assert_eq!(
+            restore(&resigned(body.replace("snapshot 1", "snapshot 2"))).unwrap_err(),
+            invalid("unsupported version 2")
+        );
+        assert_eq!(
+            restore(&resigned(body.replace("rel_base 0\n", ""))).unwrap_err(),
+            invalid("missing rel_base")
+        );
+        assert_eq!(
+            restore(&resigned(body.replace("rel_base 0", "rel_base -4"))).unwrap_err(),
+            invalid("bad rel_base \"-4\"")
+        );
+        assert_eq!(
+            restore(&resigned(body.replace("halted", "asleep"))).unwrap_err(),
+            invalid("unknown status \"asleep\"")
+        );
+        assert_eq!(
+            restore(&resigned(format!("{}mem 1 5\n", body))).unwrap_err(),
+            invalid("mem at 1 overlaps earlier mem")
+        );
+        assert_eq!(restore(&resigned(format!("{}mem 10 5\n", body))).unwrap().peek(10), 5);
+        assert_eq!(
+            restore(&resigned(format!("{}mem 18446744073709551615 1,2\n", body))).unwrap_err(),
+            invalid("bad mem start \"18446744073709551615\"")
+        );
+        assert_eq!(
+            restore(&resigned(format!("{}mem 9223372036854775807 1,2\n", body))).unwrap_err(),
+            invalid("mem at 9223372036854775807 runs past the last address")
+        );
+        let far = restore(&resigned(format!("{}mem 5000000000 5,0,6\n", body))).unwrap();
+        assert_eq!(far.peek(5_000_000_002), 6);
+        assert_eq!(restore(&snapshot(&far).unwrap()).unwrap().data, far.data);
+        assert_eq!(
+            restore(&resigned(body.replace("pos 2", "pos 0"))).unwrap_err(),
+            invalid("status Halted does not match the instruction at pos 0")
+        );
+
+        let mut faulted = Intcode::from_data(vec![42]);
+        assert!(run(&mut faulted).is_err());
+        assert!(snapshot(&faulted).is_err());
+    }
+}
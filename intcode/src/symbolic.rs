@@ -0,0 +1,557 @@
+use super::limits::{run_limited, Limits};
+use super::{Arg, Intcode, Op, ParameterMode, Status, Step};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
+
+// Solving is concolic: the program runs concretely with every unknown at the start
+// of its range, while a `Value` is kept for each memory cell whose value depends on
+// the unknowns. Jumps, addresses and rel_base take their concrete values, so the
+// value only holds along that one path and every answer is checked by running
+// the program for real.
+
+// Deepest non-linear expression tracked before giving up on the symbolic pass
+const MAX_DEPTH: usize = 100;
+
+/// A value the program computes from the unknowns, which are numbered `x0`, `x1`, ...
+/// in the order they are given in `Problem::unknowns`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Const(i64),
+    Var(usize),
+    Add(Rc<Expr>, Rc<Expr>),
+    Mul(Rc<Expr>, Rc<Expr>),
+    Lt(Rc<Expr>, Rc<Expr>),
+    Eq(Rc<Expr>, Rc<Expr>),
+}
+
+// A sum of `coefs[var] * var` and a constant
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Linear {
+    constant: i64,
+    coefs: BTreeMap<usize, i64>,
+}
+
+impl Linear {
+    fn constant(val: i64) -> Linear {
+        Linear {
+            constant: val,
+            coefs: BTreeMap::new(),
+        }
+    }
+
+    fn add(mut self, other: &Linear) -> Option<Linear> {
+        self.constant = self.constant.checked_add(other.constant)?;
+        for (var, coef) in &other.coefs {
+            let sum = self.coefs.entry(*var).or_insert(0);
+            *sum = sum.checked_add(*coef)?;
+        }
+        self.coefs.retain(|_, coef| *coef != 0);
+        Some(self)
+    }
+
+    fn scale(mut self, by: i64) -> Option<Linear> {
+        self.constant = self.constant.checked_mul(by)?;
+        for coef in self.coefs.values_mut() {
+            *coef = coef.checked_mul(by)?;
+        }
+        self.coefs.retain(|_, coef| *coef != 0);
+        Some(self)
+    }
+
+    // The terms in order of the unknowns, then the constant
+    fn to_expr(&self) -> Expr {
+        let mut terms = self.coefs.iter().map(|(var, coef)| match coef {
+            1 => Expr::Var(*var),
+            _ => Expr::Mul(Rc::new(Expr::Var(*var)), Rc::new(Expr::Const(*coef))),
+        });
+        let sum = match terms.next() {
+            Some(first) => terms.fold(first, |sum, term| Expr::Add(Rc::new(sum), Rc::new(term))),
+            None => return Expr::Const(self.constant),
+        };
+        match self.constant {
+            0 => sum,
+            val => Expr::Add(Rc::new(sum), Rc::new(Expr::Const(val))),
+        }
+    }
+}
+
+impl Expr {
+    fn binary(op: Op, a: Rc<Expr>, b: Rc<Expr>) -> Expr {
+        match op {
+            Op::Add => Expr::Add(a, b),
+            Op::Mul => Expr::Mul(a, b),
+            Op::LessThan => Expr::Lt(a, b),
+            _ => Expr::Eq(a, b),
+        }
+    }
+
+    /// The value of the expression with `values[i]` for `xi`, or `None` if it overflows.
+    pub fn eval(&self, values: &[i64]) -> Option<i64> {
+        match self {
+            Expr::Const(val) => Some(*val),
+            Expr::Var(var) => Some(values[*var]),
+            Expr::Add(a, b) => a.eval(values)?.checked_add(b.eval(values)?),
+            Expr::Mul(a, b) => a.eval(values)?.checked_mul(b.eval(values)?),
+            Expr::Lt(a, b) => Some((a.eval(values)? < b.eval(values)?) as i64),
+            Expr::Eq(a, b) => Some((a.eval(values)? == b.eval(values)?) as i64),
+        }
+    }
+
+    // None for anything that isn't linear in the unknowns, or overflows
+    fn linear(&self) -> Option<Linear> {
+        match self {
+            Expr::Const(val) => Some(Linear::constant(*val)),
+            Expr::Var(var) => Some(Linear {
+                constant: 0,
+                coefs: vec![(*var, 1)].into_iter().collect(),
+            }),
+            Expr::Add(a, b) => a.linear()?.add(&b.linear()?),
+            Expr::Mul(a, b) => {
+                let (a, b) = (a.linear()?, b.linear()?);
+                match (a.coefs.is_empty(), b.coefs.is_empty()) {
+                    (true, _) => b.scale(a.constant),
+                    (_, true) => a.scale(b.constant),
+                    _ => None,
+                }
+            }
+            Expr::Lt(..) | Expr::Eq(..) => None,
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Const(val) => write!(f, "{}", val),
+            Expr::Var(var) => write!(f, "x{}", var),
+            Expr::Add(a, b) => write!(f, "({} + {})", a, b),
+            Expr::Mul(a, b) => write!(f, "({} * {})", a, b),
+            Expr::Lt(a, b) => write!(f, "({} < {})", a, b),
+            Expr::Eq(a, b) => write!(f, "({} == {})", a, b),
+        }
+    }
+}
+
+// What the symbolic pass knows about a value. Linear values are kept as `Linear`, so
+// a loop that keeps adding to a cell costs the same every time around; anything else
+// is a tree of shared nodes, along with its depth.
+#[derive(Debug, Clone)]
+enum Value {
+    Linear(Linear),
+    Tree(Rc<Expr>, usize),
+}
+
+impl Value {
+    fn constant(&self) -> Option<i64> {
+        match self {
+            Value::Linear(linear) if linear.coefs.is_empty() => Some(linear.constant),
+            _ => None,
+        }
+    }
+
+    fn expr(&self) -> Rc<Expr> {
+        match self {
+            Value::Linear(linear) => Rc::new(linear.to_expr()),
+            Value::Tree(expr, _) => expr.clone(),
+        }
+    }
+
+    fn depth(&self) -> usize {
+        match self {
+            Value::Linear(linear) => linear.coefs.len() + 2,
+            Value::Tree(_, depth) => *depth,
+        }
+    }
+
+    fn linear(&self) -> Option<Linear> {
+        match self {
+            Value::Linear(linear) => Some(linear.clone()),
+            Value::Tree(expr, _) => expr.linear(),
+        }
+    }
+
+    // `op(a, b)`, or `None` if it overflows or gets too deep to be worth tracking
+    fn binary(op: Op, a: &Value, b: &Value) -> Option<Value> {
+        if let (Some(a), Some(b)) = (a.constant(), b.constant()) {
+            let val = match op {
+                Op::Add => a.checked_add(b)?,
+                Op::Mul => a.checked_mul(b)?,
+                Op::LessThan => (a < b) as i64,
+                _ => (a == b) as i64,
+            };
+            return Some(Value::Linear(Linear::constant(val)));
+        }
+        if let (Value::Linear(x), Value::Linear(y)) = (a, b) {
+            let linear = match op {
+                Op::Add => x.clone().add(y),
+                Op::Mul if x.coefs.is_empty() => y.clone().scale(x.constant),
+                Op::Mul if y.coefs.is_empty() => x.clone().scale(y.constant),
+                _ => None,
+            };
+            if let Some(linear) = linear {
+                return Some(Value::Linear(linear));
+            }
+        }
+        let depth = a.depth().max(b.depth()) + 1;
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        Some(Value::Tree(Rc::new(Expr::binary(op, a.expr(), b.expr())), depth))
+    }
+}
+
+/// Something the program starts out with that the solver gets to choose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unknown {
+    /// The initial value of a memory cell.
+    Cell(usize),
+    /// The input value read `n`th, counting from zero.
+    Input(usize),
+}
+
+/// Where the value that has to come out as the goal ends up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// A memory cell once the program stops.
+    Cell(usize),
+    /// The value output `n`th, counting from zero.
+    Output(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct Problem {
+    /// Fed to every run, with `Unknown::Input` values put in place.
+    pub input: Vec<i64>,
+    pub unknowns: Vec<(Unknown, Range<i64>)>,
+    pub target: Target,
+    pub goal: i64,
+    /// Bounds every run of the program, so a wrong guess can't hang the search.
+    pub limits: Limits,
+}
+
+impl Problem {
+    pub fn new(unknowns: Vec<(Unknown, Range<i64>)>, target: Target, goal: i64) -> Problem {
+        Problem {
+            input: Vec::new(),
+            unknowns,
+            target,
+            goal,
+            limits: Limits::steps(1_000_000),
+        }
+    }
+
+    fn ranges(&self) -> Vec<Range<i64>> {
+        self.unknowns.iter().map(|(_, range)| range.clone()).collect()
+    }
+
+    // A fork of the machine with `values` filled in for the unknowns and its input queued
+    fn instantiate(&self, code: &Intcode, values: &[i64]) -> Intcode {
+        let mut code = code.fork();
+        let mut input = self.input.clone();
+        for ((unknown, _), val) in self.unknowns.iter().zip(values) {
+            match *unknown {
+                Unknown::Cell(addr) => code.poke(addr, *val),
+                Unknown::Input(n) => {
+                    if input.len() <= n {
+                        input.resize(n + 1, 0);
+                    }
+                    input[n] = *val;
+                }
+            }
+        }
+        code.input.extend(input);
+        code
+    }
+
+    /// Whether running the program with `values` for the unknowns hits the goal.
+    pub fn check(&self, code: &Intcode, values: &[i64]) -> bool {
+        let mut code = self.instantiate(code, values);
+        match run_limited(&mut code, &self.limits) {
+            Ok(Status::Halted) | Ok(Status::AwaitingInput) => {}
+            _ => return false,
+        }
+        match self.target {
+            Target::Cell(addr) => code.peek(addr) == self.goal,
+            Target::Output(n) => code.output.get(n) == Some(&self.goal),
+        }
+    }
+}
+
+/// How `solve` came up with its answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// Solved the target's expression, which was linear.
+    Linear,
+    /// Tried every combination of values in order.
+    Search,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    /// One value per unknown.
+    pub values: Vec<i64>,
+    pub method: Method,
+}
+
+// The memory address an operand refers to, if it isn't immediate
+fn address(code: &Intcode, arg: Arg) -> Option<usize> {
+    let addr = match arg.mode {
+        ParameterMode::Position => arg.value,
+        ParameterMode::Relative => arg.value.checked_add(code.rel_base as i64)?,
+        ParameterMode::Immediate => return None,
+    };
+    if addr < 0 {
+        None
+    } else {
+        Some(addr as usize)
+    }
+}
+
+/// Runs the program once with every unknown at the start of its range and returns
+/// the target as an expression in the unknowns, or `None` if the run never gets to
+/// it or the expression grows too deep to track. The expression only holds for
+/// inputs that take the same path through the program.
+pub fn symbolic(code: &Intcode, problem: &Problem) -> Option<Expr> {
+    trace(code, problem).map(|value| (*value.expr()).clone())
+}
+
+fn trace(code: &Intcode, problem: &Problem) -> Option<Value> {
+    let start: Vec<i64> = problem.unknowns.iter().map(|(_, range)| range.start).collect();
+    let mut code = problem.instantiate(code, &start);
+    let var = |var| {
+        Value::Linear(Linear {
+            constant: 0,
+            coefs: vec![(var, 1)].into_iter().collect(),
+        })
+    };
+    // Cells and input values that depend on the unknowns
+    let mut cells: HashMap<usize, Value> = HashMap::new();
+    let mut inputs: HashMap<usize, Value> = HashMap::new();
+    for (n, (unknown, _)) in problem.unknowns.iter().enumerate() {
+        match *unknown {
+            Unknown::Cell(addr) => cells.insert(addr, var(n)),
+            Unknown::Input(i) => inputs.insert(i, var(n)),
+        };
+    }
+    let mut read = 0;
+    let mut outputs = Vec::new();
+
+    for _ in 0..problem.limits.max_steps.unwrap_or(u64::MAX) {
+        let op = code.op().ok()?;
+        let value_of = |arg: Arg| match address(&code, arg) {
+            Some(addr) => cells
+                .get(&addr)
+                .cloned()
+                .unwrap_or_else(|| Value::Linear(Linear::constant(code.peek(addr)))),
+            None => Value::Linear(Linear::constant(arg.value)),
+        };
+        let result = match op.opcode {
+            Op::Add | Op::Mul | Op::LessThan | Op::Equals => {
+                Some(Value::binary(op.opcode, &value_of(op.args[0]), &value_of(op.args[1]))?)
+            }
+            Op::Read => inputs.remove(&read),
+            Op::Write => Some(value_of(op.args[0])),
+            _ => None,
+        };
+        let target = op.opcode.write_arg().and_then(|i| address(&code, op.args[i]));
+
+        match code.step().ok()? {
+            Step::Continue => {}
+            Step::Output(_) => outputs.extend(result.clone()),
+            Step::NeedInput | Step::Halted => break,
+        }
+        if op.opcode == Op::Read {
+            read += 1;
+        }
+        if let Some(addr) = target {
+            match result {
+                Some(value) if value.constant().is_none() => cells.insert(addr, value),
+                _ => cells.remove(&addr),
+            };
+        }
+    }
+    match problem.target {
+        Target::Cell(addr) if code.status() != &Status::Running => {
+            Some(cells.remove(&addr).unwrap_or_else(|| Value::Linear(Linear::constant(code.peek(addr)))))
+        }
+        Target::Output(n) => outputs.into_iter().nth(n),
+        _ => None,
+    }
+}
+
+// Every combination of values in the ranges, the first unknown changing slowest
+fn combinations(ranges: Vec<Range<i64>>) -> impl Iterator<Item = Vec<i64>> {
+    let mut next = if ranges.iter().all(|range| !range.is_empty()) {
+        Some(ranges.iter().map(|range| range.start).collect::<Vec<i64>>())
+    } else {
+        None
+    };
+    std::iter::from_fn(move || {
+        let current = next.take()?;
+        let mut following = current.clone();
+        for i in (0..ranges.len()).rev() {
+            following[i] += 1;
+            if following[i] < ranges[i].end {
+                next = Some(following);
+                break;
+            }
+            following[i] = ranges[i].start;
+        }
+        Some(current)
+    })
+}
+
+// Candidates for a linear target: every combination of the other unknowns, with
+// the one with the largest coefficient worked out from them. Combinations whose
+// sums overflow can't be solved for and are skipped.
+fn solve_linear<'a>(linear: &'a Linear, problem: &'a Problem) -> impl Iterator<Item = Vec<i64>> + 'a {
+    let ranges = problem.ranges();
+    let solved = linear.coefs.iter().max_by_key(|(_, coef)| coef.unsigned_abs()).map(|(var, _)| *var);
+    let mut others = ranges.clone();
+    if let Some(var) = solved {
+        others[var] = 0..1;
+    }
+    combinations(others).filter_map(move |mut values| {
+        let mut rest = problem.goal.checked_sub(linear.constant)?;
+        for (var, coef) in &linear.coefs {
+            if Some(*var) != solved {
+                rest = rest.checked_sub(coef.checked_mul(values[*var])?)?;
+            }
+        }
+        match solved {
+            Some(var) => {
+                let coef = linear.coefs[&var];
+                let val = rest.checked_div(coef)?;
+                if rest.checked_rem(coef)? != 0 || !ranges[var].contains(&val) {
+                    return None;
+                }
+                values[var] = val;
+                Some(values)
+            }
+            None if rest == 0 => Some(values),
+            None => None,
+        }
+    })
+}
+
+/// Finds values for the unknowns that make the target come out as the goal. A
+/// target that is linear in the unknowns is solved directly; anything else, an
+/// expression too deep to track, or a linear answer that doesn't hold up when the
+/// program is run, falls back to trying every combination of values.
+pub fn solve(code: &Intcode, problem: &Problem) -> Option<Solution> {
+    if let Some(linear) = trace(code, problem).and_then(|value| value.linear()) {
+        if let Some(values) = solve_linear(&linear, problem).find(|values| problem.check(code, values)) {
+            return Some(Solution {
+                values,
+                method: Method::Linear,
+            });
+        }
+    }
+    combinations(problem.ranges())
+        .find(|values| problem.check(code, values))
+        .map(|values| Solution {
+            values,
+            method: Method::Search,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{solve, solve_linear, symbolic, Method, Problem, Target, Unknown};
+    use crate::asm::assemble;
+    use crate::Intcode;
+
+    #[test]
+    fn linear_and_searched() {
+        // Day 2 style: the result is a * 1000 + b + 7, from cells patched before running
+        let program = assemble(
+            "
+            MUL [a], #1000, [r]
+            ADD [r], [b], [r]
+            ADD [r], #7, [r]
+            HLT
+            r: DATA 0
+            a: DATA 0
+            b: DATA 0
+            ",
+        )
+        .unwrap();
+        let code = Intcode::from_data(program);
+        let unknowns = vec![(Unknown::Cell(14), 0..100), (Unknown::Cell(15), 0..100)];
+        let problem = Problem::new(unknowns, Target::Cell(13), 42_019);
+        let expr = symbolic(&code, &problem).unwrap();
+        assert_eq!(expr.to_string(), "(((x0 * 1000) + x1) + 7)");
+        assert_eq!(expr.eval(&[42, 12]), Some(42_019));
+        assert_eq!(expr.eval(&[i64::MAX, 0]), None);
+        let solution = solve(&code, &problem).unwrap();
+        assert_eq!((solution.values, solution.method), (vec![42, 12], Method::Linear));
+        // Candidates whose sums overflow are skipped
+        let linear = expr.linear().unwrap();
+        let wide = vec![(Unknown::Cell(14), 0..10), (Unknown::Cell(15), i64::MAX - 10..i64::MAX)];
+        assert_eq!(solve_linear(&linear, &Problem::new(wide, Target::Cell(13), -42)).count(), 0);
+
+        // Squares its input and outputs whether that's 49
+        let program = assemble(
+            "
+            IN [x]
+            MUL [x], [x], [x]
+            EQ [x], #49, [x]
+            OUT [x]
+            HLT
+            x: DATA 0
+            ",
+        )
+        .unwrap();
+        let code = Intcode::from_data(program);
+        let problem = Problem::new(vec![(Unknown::Input(0), 0..10)], Target::Output(0), 1);
+        assert_eq!(symbolic(&code, &problem).unwrap().to_string(), "((x0 * x0) == 49)");
+        let solution = solve(&code, &problem).unwrap();
+        assert_eq!((solution.values, solution.method), (vec![7], Method::Search));
+        let problem = Problem::new(vec![(Unknown::Input(0), 0..7)], Target::Output(0), 1);
+        assert_eq!(solve(&code, &problem), None);
+    }
+
+    #[test]
+    fn long_loops() {
+        // Adds x to acc 100,000 times, which stays linear however often it goes round
+        let program = assemble(
+            "
+            loop:   ADD [acc], [x], [acc]
+                    ADD [n], #-1, [n]
+                    JT [n], #loop
+                    HLT
+            acc:    DATA 3
+            x:      DATA 0
+            n:      DATA 100000
+            ",
+        )
+        .unwrap();
+        let code = Intcode::from_data(program);
+        let problem = Problem::new(vec![(Unknown::Cell(13), 0..10)], Target::Cell(12), 700_003);
+        assert_eq!(symbolic(&code, &problem).unwrap().to_string(), "((x0 * 100000) + 3)");
+        let solution = solve(&code, &problem).unwrap();
+        assert_eq!((solution.values, solution.method), (vec![7], Method::Linear));
+
+        // Raises x to the 200th power, too deep to track, so it's searched instead
+        let program = assemble(
+            "
+            loop:   MUL [acc], [x], [acc]
+                    ADD [n], #-1, [n]
+                    JT [n], #loop
+                    OUT [acc]
+                    HLT
+            acc:    DATA 1
+            x:      DATA 0
+            n:      DATA 200
+            ",
+        )
+        .unwrap();
+        let code = Intcode::from_data(program);
+        let problem = Problem::new(vec![(Unknown::Cell(15), -1..2)], Target::Output(0), 1);
+        assert_eq!(symbolic(&code, &problem), None);
+        let solution = solve(&code, &problem).unwrap();
+        assert_eq!((solution.values, solution.method), (vec![-1], Method::Search));
+    }
+}
@@ -0,0 +1,227 @@
+use super::disasm::format_operation;
+use super::{run_observed, Intcode, IntcodeError, Observer, ParameterMode, Status, Step};
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+/// One executed instruction. `args` holds the values that were read, in order;
+/// the stored result shows up in `writes` instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub cycle: u64,
+    pub addr: usize,
+    pub op: String,
+    pub args: Vec<i64>,
+    pub writes: Vec<(usize, i64)>,
+    pub rel_base: Option<usize>,
+    pub input: Option<i64>,
+    pub output: Option<i64>,
+}
+
+impl TraceEntry {
+    pub fn to_json(&self) -> String {
+        let args: Vec<String> = self.args.iter().map(|a| a.to_string()).collect();
+        let writes: Vec<String> = self
+            .writes
+            .iter()
+            .map(|(addr, val)| format!("[{},{}]", addr, val))
+            .collect();
+        let mut json = format!(
+            "{{\"cycle\":{},\"addr\":{},\"op\":{:?},\"args\":[{}],\"writes\":[{}]",
+            self.cycle,
+            self.addr,
+            self.op,
+            args.join(","),
+            writes.join(",")
+        );
+        if let Some(rel_base) = self.rel_base {
+            json += &format!(",\"rel_base\":{}", rel_base);
+        }
+        if let Some(input) = self.input {
+            json += &format!(",\"input\":{}", input);
+        }
+        if let Some(output) = self.output {
+            json += &format!(",\"output\":{}", output);
+        }
+        json + "}"
+    }
+}
+
+/// Collects a `TraceEntry` for every instruction executed through it.
+#[derive(Debug, Default)]
+pub struct Tracer {
+    cycle: u64,
+    pub entries: Vec<TraceEntry>,
+}
+
+// Builds entries from what the machine reports. Arguments are collected in order,
+// immediates as they were decoded and everything else from the reads.
+struct Recorder<'a> {
+    tracer: &'a mut Tracer,
+    rel_base: usize,
+    entry: Option<TraceEntry>,
+    args: Vec<Option<i64>>,
+}
+
+impl Recorder<'_> {
+    fn new(tracer: &mut Tracer, rel_base: usize) -> Recorder<'_> {
+        Recorder {
+            tracer,
+            rel_base,
+            entry: None,
+            args: Vec::new(),
+        }
+    }
+
+    // Adds the instruction in progress to the trace, once it has finished
+    fn flush(&mut self) {
+        if let Some(mut entry) = self.entry.take() {
+            entry.args = self.args.drain(..).flatten().collect();
+            self.tracer.entries.push(entry);
+            self.tracer.cycle += 1;
+        }
+    }
+
+    fn update<F: FnOnce(&mut TraceEntry)>(&mut self, f: F) {
+        if let Some(entry) = self.entry.as_mut() {
+            f(entry);
+        }
+    }
+}
+
+impl Observer for Recorder<'_> {
+    fn fetch(&mut self, addr: usize, instruction: &[i64]) {
+        self.flush();
+        let op = match Intcode::decode_with(addr, |i| instruction[i]) {
+            Ok(op) => op,
+            Err(_) => return,
+        };
+        let write_arg = op.opcode.write_arg();
+        self.args = op.args[..op.opcode.arity()]
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| Some(*i) != write_arg)
+            .map(|(_, arg)| match arg.mode {
+                ParameterMode::Immediate => Some(arg.value),
+                _ => None,
+            })
+            .collect();
+        self.entry = Some(TraceEntry {
+            cycle: self.tracer.cycle,
+            addr,
+            op: format_operation(&op),
+            args: Vec::new(),
+            writes: Vec::new(),
+            rel_base: None,
+            input: None,
+            output: None,
+        });
+    }
+
+    fn read(&mut self, _addr: usize, val: i64) {
+        if let Some(arg) = self.args.iter_mut().find(|arg| arg.is_none()) {
+            *arg = Some(val);
+        }
+    }
+
+    fn write(&mut self, addr: usize, val: i64) {
+        self.update(|entry| entry.writes.push((addr, val)));
+    }
+
+    fn input(&mut self, val: i64) {
+        self.update(|entry| entry.input = Some(val));
+    }
+
+    fn output(&mut self, val: i64) {
+        self.update(|entry| entry.output = Some(val));
+    }
+
+    fn adjust(&mut self, rel_base: usize) {
+        if rel_base != self.rel_base {
+            self.rel_base = rel_base;
+            self.update(|entry| entry.rel_base = Some(rel_base));
+        }
+    }
+}
+
+impl Tracer {
+    pub fn new() -> Tracer {
+        Tracer::default()
+    }
+
+    /// Like `Intcode::step`, recording what the instruction did. An instruction
+    /// that faults isn't recorded.
+    pub fn step(&mut self, code: &mut Intcode) -> Result<Step, IntcodeError> {
+        let mut recorder = Recorder::new(self, code.rel_base);
+        let step = code.step_with(&mut recorder)?;
+        recorder.flush();
+        Ok(step)
+    }
+
+    /// Like `run`, tracing every instruction.
+    pub fn run(&mut self, code: &mut Intcode) -> Result<Status, IntcodeError> {
+        let mut recorder = Recorder::new(self, code.rel_base);
+        let status = run_observed(code, &mut recorder)?;
+        recorder.flush();
+        Ok(status)
+    }
+
+    pub fn write_jsonl<W: Write>(&self, mut out: W) -> io::Result<()> {
+        for entry in &self.entries {
+            writeln!(out, "{}", entry.to_json())?;
+        }
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, file: P) -> io::Result<()> {
+        self.write_jsonl(io::BufWriter::new(File::create(file)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tracer;
+    use crate::asm::assemble;
+    use crate::{Intcode, Status};
+
+    #[test]
+    fn traces_as_json_lines() {
+        let program = assemble(
+            "
+            IN [x]
+            ARB #2
+            MUL [x], #3, [rb+5]
+            OUT [7]
+            HLT
+            x: DATA 0
+            ",
+        )
+        .unwrap();
+        let mut code = Intcode::from_data(program);
+        code.push_input(14);
+        let mut tracer = Tracer::new();
+        assert_eq!(tracer.run(&mut code), Ok(Status::Halted));
+        assert_eq!(code.output, vec![42]);
+        let mut out = Vec::new();
+        tracer.write_jsonl(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+{\"cycle\":0,\"addr\":0,\"op\":\"IN [11]\",\"args\":[],\"writes\":[[11,14]],\"input\":14}
+{\"cycle\":1,\"addr\":2,\"op\":\"ARB #2\",\"args\":[2],\"writes\":[],\"rel_base\":2}
+{\"cycle\":2,\"addr\":4,\"op\":\"MUL [11], #3, [rb+5]\",\"args\":[14,3],\"writes\":[[7,42]]}
+{\"cycle\":3,\"addr\":8,\"op\":\"OUT [7]\",\"args\":[42],\"writes\":[],\"output\":42}
+{\"cycle\":4,\"addr\":10,\"op\":\"HLT\",\"args\":[],\"writes\":[]}
+"
+        );
+
+        // Instructions that fault aren't traced
+        let mut code = Intcode::from_data(vec![1101, 2, 3, 7, 203, -1, 99, 0]);
+        code.push_input(1);
+        let mut tracer = Tracer::new();
+        assert!(tracer.run(&mut code).is_err());
+        assert_eq!(tracer.entries.len(), 1);
+        assert_eq!(tracer.entries[0].args, vec![2, 3]);
+    }
+}
@@ -0,0 +1,101 @@
+// Generated by intcode::compile::compile, do not edit.
+
+use intcode::compile::{Compiled, Exit};
+
+static PROGRAM: [i64; 26] = [
+    109, 25, 203, 0, 204, 0, 21201, 0, -1, 0, 1205, 0, 4, 1101, 1102, 0,
+    17, 1101, 6, 7, 24, 4, 24, 99, 0, 0
+];
+
+// Words that belong to compiled instructions
+static CODE_WORDS: [bool; 26] = [
+    true, true, true, true, true, true, true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, false, false
+];
+
+pub fn new() -> Compiled {
+    Compiled::new(&PROGRAM, &CODE_WORDS, exec)
+}
+
+#[allow(unused_variables, clippy::all)]
+fn exec(m: &mut Compiled) -> Exit {
+    match m.pos() {
+        0 => {
+            if m.stale(0, 4) {
+                return Exit::Interpret;
+            }
+            // 0: ARB #25
+            let a: i64 = 25;
+            if !m.adjust_rel_base(a) {
+                return m.interpret(0);
+            }
+            // 2: IN [rb]
+            let t = match m.rel(0) { Some(addr) => addr, None => return m.interpret(2) };
+            let a = match m.input() { Some(val) => val, None => return m.interpret(2) };
+            if m.write(t, a) {
+                return m.next(4);
+            }
+            return m.next(4);
+        }
+        4 => {
+            if m.stale(4, 6) {
+                return Exit::Interpret;
+            }
+            // 4: OUT [rb]
+            let a = match m.rel(0) { Some(addr) => m.read(addr), None => return m.interpret(4) };
+            return m.output(6, a);
+        }
+        6 => {
+            if m.stale(6, 13) {
+                return Exit::Interpret;
+            }
+            // 6: ADD [rb], #-1, [rb]
+            let a = match m.rel(0) { Some(addr) => m.read(addr), None => return m.interpret(6) };
+            let b: i64 = -1;
+            let t = match m.rel(0) { Some(addr) => addr, None => return m.interpret(6) };
+            let c = match a.checked_add(b) { Some(val) => val, None => return m.interpret(6) };
+            if m.write(t, c) {
+                return m.next(10);
+            }
+            // 10: JT [rb], #4
+            let a = match m.rel(0) { Some(addr) => m.read(addr), None => return m.interpret(10) };
+            if a != 0 {
+                let b: i64 = 4;
+                return m.goto(10, b);
+            }
+            return m.next(13);
+        }
+        13 => {
+            if m.stale(13, 23) {
+                return Exit::Interpret;
+            }
+            // 13: ADD #1102, #0, [17]
+            let a: i64 = 1102;
+            let b: i64 = 0;
+            let t = 17;
+            let c = match a.checked_add(b) { Some(val) => val, None => return m.interpret(13) };
+            if m.write(t, c) {
+                return m.next(17);
+            }
+            // 17: ADD #6, #7, [24]
+            let a: i64 = 6;
+            let b: i64 = 7;
+            let t = 24;
+            let c = match a.checked_add(b) { Some(val) => val, None => return m.interpret(17) };
+            if m.write(t, c) {
+                return m.next(21);
+            }
+            // 21: OUT [24]
+            let a = m.read(24);
+            return m.output(23, a);
+        }
+        23 => {
+            if m.stale(23, 24) {
+                return Exit::Interpret;
+            }
+            // 23: HLT
+            return m.interpret(23);
+        }
+        _ => Exit::Interpret,
+    }
+}